@@ -1,10 +1,60 @@
+mod manifest;
+
+use manifest::Report;
+use std::env;
 use std::fs::File;
 use std::io::{BufRead, BufReader, Result};
 
 fn main() -> Result<()> {
-    let input = read_input("input")?;
-    challenge_1(&input);
-    challenge_2(&input);
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(|s| s.as_str()) {
+        None => {
+            let input = read_input("input")?;
+            challenge_1(&input);
+            challenge_2(&input);
+        }
+        Some("report") if args.len() == 3 || args.len() == 4 => {
+            report(&args[2], args.get(3).map_or("standard", |s| s.as_str()))?
+        }
+        _ => usage(),
+    }
+    Ok(())
+}
+
+fn usage() -> ! {
+    let program = env::args().next().unwrap();
+    println!("Usage:");
+    println!("    {}", program);
+    println!("    {} report MANIFEST [FORMULA]", program);
+    println!();
+    println!("Arguments:");
+    println!("    manifest: CSV file of module names and masses");
+    let names: Vec<&str> = manifest::FORMULAS.iter().map(|(n, _)| *n).collect();
+    println!("    formula: {} (default standard)", names.join(", "));
+    std::process::exit(1);
+}
+
+fn report(path: &str, formula: &str) -> Result<()> {
+    let formula = manifest::formula(formula).unwrap_or_else(|| {
+        eprintln!("unknown formula {}", formula);
+        usage();
+    });
+    let text = std::fs::read_to_string(path)?;
+    let modules = manifest::parse_manifest(&text).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        std::process::exit(1);
+    });
+    let report = Report::new(&modules, formula).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        std::process::exit(1);
+    });
+    print!("{}", report.to_csv());
+    for module in report.flagged() {
+        eprintln!(
+            "warning: module {} (mass {}) yields {} fuel",
+            module.name, module.mass, module.base
+        );
+    }
     Ok(())
 }
 
@@ -0,0 +1,234 @@
+/// Calculates the fuel needed to launch a mass.
+pub type Formula = fn(i32) -> i32;
+
+pub fn standard(mass: i32) -> i32 {
+    mass / 3 - 2
+}
+
+pub fn rounded(mass: i32) -> i32 {
+    (mass as f64 / 3.0).round() as i32 - 2
+}
+
+pub const FORMULAS: [(&str, Formula); 2] = [("standard", standard), ("rounded", rounded)];
+
+pub fn formula(name: &str) -> Option<Formula> {
+    FORMULAS.iter().find(|(n, _)| *n == name).map(|(_, f)| *f)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Module {
+    pub name: String,
+    pub mass: i32,
+}
+
+/// Parses `name,mass` lines, skipping blank lines and an optional header.
+pub fn parse_manifest(text: &str) -> Result<Vec<Module>, String> {
+    let mut modules = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || (i == 0 && line.to_lowercase() == "name,mass") {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        let (name, mass) = match fields.as_slice() {
+            [name, mass] if !name.is_empty() => (name, mass),
+            _ => return Err(format!("line {}: expected name,mass", i + 1)),
+        };
+        let mass = mass
+            .parse()
+            .map_err(|_| format!("line {}: invalid mass {:?}", i + 1, mass))?;
+        modules.push(Module {
+            name: name.to_string(),
+            mass,
+        });
+    }
+    Ok(modules)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ModuleFuel {
+    pub name: String,
+    pub mass: i32,
+    pub base: i32,
+    pub fuel_for_fuel: i32,
+    pub iterations: u32,
+}
+
+impl ModuleFuel {
+    pub fn total(&self) -> i32 {
+        self.base.max(0) + self.fuel_for_fuel
+    }
+
+    /// True if the module's mass is too small to need any fuel.
+    pub fn needs_no_fuel(&self) -> bool {
+        self.base <= 0
+    }
+}
+
+// Applies the formula to the module's mass and then to each increment of fuel until the
+// fuel needed is no longer positive. `iterations` counts the positive increments. Fails if
+// an increment doesn't shrink, as the fuel would never stop growing.
+fn module_fuel(module: &Module, formula: Formula) -> Result<ModuleFuel, String> {
+    let base = formula(module.mass);
+    let mut fuel_for_fuel = 0;
+    let mut iterations = 0;
+    let mut fuel = base;
+    while fuel > 0 {
+        iterations += 1;
+        let next = formula(fuel);
+        if next >= fuel {
+            return Err(format!(
+                "fuel formula does not converge for module {} (mass {})",
+                module.name, module.mass
+            ));
+        }
+        if next > 0 {
+            fuel_for_fuel += next;
+        }
+        fuel = next;
+    }
+    Ok(ModuleFuel {
+        name: module.name.clone(),
+        mass: module.mass,
+        base,
+        fuel_for_fuel,
+        iterations,
+    })
+}
+
+#[derive(Debug)]
+pub struct Report {
+    pub modules: Vec<ModuleFuel>,
+}
+
+impl Report {
+    pub fn new(modules: &[Module], formula: Formula) -> Result<Self, String> {
+        Ok(Self {
+            modules: modules
+                .iter()
+                .map(|m| module_fuel(m, formula))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn base_total(&self) -> i32 {
+        self.modules.iter().map(|m| m.base.max(0)).sum()
+    }
+
+    pub fn fuel_for_fuel_total(&self) -> i32 {
+        self.modules.iter().map(|m| m.fuel_for_fuel).sum()
+    }
+
+    pub fn total(&self) -> i32 {
+        self.modules.iter().map(|m| m.total()).sum()
+    }
+
+    pub fn flagged(&self) -> Vec<&ModuleFuel> {
+        self.modules.iter().filter(|m| m.needs_no_fuel()).collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("module,mass,base_fuel,fuel_for_fuel,total_fuel,iterations\n");
+        for m in &self.modules {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                m.name,
+                m.mass,
+                m.base,
+                m.fuel_for_fuel,
+                m.total(),
+                m.iterations
+            ));
+        }
+        csv.push_str(&format!(
+            "TOTAL,{},{},{},{},\n",
+            self.modules.iter().map(|m| m.mass).sum::<i32>(),
+            self.base_total(),
+            self.fuel_for_fuel_total(),
+            self.total()
+        ));
+        csv
+    }
+}
+
+#[cfg(test)]
+mod manifest_tests {
+    use super::*;
+
+    fn module(name: &str, mass: i32) -> Module {
+        Module {
+            name: name.to_string(),
+            mass,
+        }
+    }
+
+    #[test]
+    fn parses_manifest() {
+        let text = "name,mass\nnav, 14\n\nlander,1969\n";
+        assert_eq!(
+            parse_manifest(text),
+            Ok(vec![module("nav", 14), module("lander", 1969)])
+        );
+        assert_eq!(
+            parse_manifest("nav,14\nlander\n"),
+            Err("line 2: expected name,mass".to_string())
+        );
+        assert_eq!(
+            parse_manifest("nav,heavy\n"),
+            Err("line 1: invalid mass \"heavy\"".to_string())
+        );
+    }
+
+    #[test]
+    fn breaks_down_fuel_per_module() {
+        let report = Report::new(&[module("a", 1969), module("b", 100756)], standard).unwrap();
+        assert_eq!(
+            report.modules[0],
+            ModuleFuel {
+                name: "a".to_string(),
+                mass: 1969,
+                base: 654,
+                fuel_for_fuel: 312,
+                iterations: 5,
+            }
+        );
+        assert_eq!(report.modules[0].total(), 966);
+        assert_eq!(report.modules[1].total(), 50346);
+        assert_eq!(report.base_total(), 654 + 33583);
+        assert_eq!(report.total(), 966 + 50346);
+    }
+
+    #[test]
+    fn flags_modules_needing_no_fuel() {
+        let report = Report::new(&[module("probe", 2), module("nav", 14)], standard).unwrap();
+        let flagged: Vec<&str> = report.flagged().iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(flagged, vec!["probe"]);
+        assert_eq!(report.modules[0].total(), 0);
+        assert_eq!(
+            report.to_csv(),
+            "module,mass,base_fuel,fuel_for_fuel,total_fuel,iterations
+probe,2,-2,0,0,0
+nav,14,2,0,2,1
+TOTAL,16,2,0,2,
+"
+        );
+    }
+
+    #[test]
+    fn compares_formulas() {
+        let modules = [module("a", 14)];
+        let total = |formula| Report::new(&modules, formula).unwrap().total();
+        assert_eq!(total(standard), 2);
+        assert_eq!(total(formula("rounded").unwrap()), 3);
+        assert_eq!(total(|mass| mass / 2 - 1), 6 + 2);
+        assert!(formula("warp").is_none());
+    }
+
+    #[test]
+    fn refuses_formulas_that_never_converge() {
+        assert_eq!(
+            Report::new(&[module("a", 10)], |mass| mass + 1).unwrap_err(),
+            "fuel formula does not converge for module a (mass 10)"
+        );
+    }
+}
@@ -1,66 +1,156 @@
+use intcode_computer::Computer;
+use std::env;
 use std::fs::File;
 use std::io::{Read, Result};
+use std::ops::{Range, RangeInclusive};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
 
-const OPCODE_HALT: u32 = 99;
-const OPCODE_ADD: u32 = 1;
-const OPCODE_MULTIPLY: u32 = 2;
+const TARGET: i64 = 19_690_720;
+// Far more than any halting patch of the puzzle program needs.
+const INSTRUCTION_BUDGET: u64 = 100_000;
 
 fn main() -> Result<()> {
     let program = read_program("input")?;
-    challenge_1(&program);
-    challenge_2(&program);
+    let args: Vec<String> = env::args().collect();
+    match args.len() {
+        1 => {
+            challenge_1(&program);
+            challenge_2(&program);
+        }
+        3 | 5 if args[1] == "search" => {
+            let target = args[2].parse().unwrap_or_else(|_| usage());
+            let (addresses, values) = if args.len() == 5 {
+                (
+                    parse_addresses(&args[3], program.len()),
+                    parse_range(&args[4]),
+                )
+            } else {
+                (1..3, (0, 99))
+            };
+            for inputs in find_inputs(&program, addresses, values.0..=values.1, target) {
+                let inputs: Vec<String> = inputs.iter().map(|v| v.to_string()).collect();
+                println!("{}", inputs.join(","));
+            }
+        }
+        _ => usage(),
+    }
     Ok(())
 }
 
-fn challenge_1(program: &[u32]) {
+fn usage() -> ! {
+    let program = env::args().next().unwrap();
+    println!("Usage:");
+    println!("    {}", program);
+    println!("    {} search TARGET [ADDRESSES VALUES]", program);
+    println!();
+    println!("Arguments:");
+    println!("    target: The value to leave at address 0");
+    println!("    addresses: The addresses to patch, e.g. 1-2 (default)");
+    println!("    values: The values to try at each address, e.g. 0-99 (default)");
+    std::process::exit(1);
+}
+
+fn parse_range(s: &str) -> (i64, i64) {
+    let mut parts = s.splitn(2, '-').map(|p| p.parse().ok());
+    match (parts.next().flatten(), parts.next().flatten()) {
+        (Some(a), Some(b)) if a <= b => (a, b),
+        _ => usage(),
+    }
+}
+
+// An inclusive range of addresses within a program of `len` cells, as a `Range`.
+fn parse_addresses(s: &str, len: usize) -> Range<usize> {
+    match parse_range(s) {
+        (a, b) if a >= 0 && (b as usize) < len => a as usize..b as usize + 1,
+        _ => usage(),
+    }
+}
+
+fn challenge_1(program: &[i64]) {
     let mut program = program.to_owned();
     program[1] = 12;
     program[2] = 2;
-    run(&mut program);
-    println!("Challenge 1: Value left at position 0 = {}", program[0]);
+    let memory = run(&program);
+    println!("Challenge 1: Value left at position 0 = {}", memory[0]);
 }
 
-fn challenge_2(program: &[u32]) {
-    let target = 19_690_720;
-    for noun in 0..=99 {
-        for verb in 0..=99 {
-            let mut program = program.to_owned();
-            program[1] = noun;
-            program[2] = verb;
-            run(&mut program);
-            if program[0] == target {
-                println!("Challenge 2: 100 * noun + verb = {}", 100 * noun + verb);
-                return;
-            }
-        }
+fn challenge_2(program: &[i64]) {
+    let solutions = find_inputs(program, 1..3, 0..=99, TARGET);
+    match solutions.first() {
+        Some(inputs) => println!(
+            "Challenge 2: 100 * noun + verb = {}",
+            100 * inputs[0] + inputs[1]
+        ),
+        None => println!("Challenge 2: no noun and verb produce {}", TARGET),
     }
 }
 
-fn read_program(filename: &str) -> Result<Vec<u32>> {
+fn read_program(filename: &str) -> Result<Vec<i64>> {
     let mut input: String = String::new();
     File::open(filename)?.read_to_string(&mut input)?;
-    let program: Vec<u32> = input
+    let program: Vec<i64> = input
         .split(',')
         .map(|v| v.trim().parse().unwrap())
         .collect();
     Ok(program)
 }
 
-fn run(program: &mut Vec<u32>) {
-    for i in (0..program.len()).step_by(4) {
-        let opcode = program[i];
-        if opcode == OPCODE_HALT {
-            break;
-        }
-        let param1 = program[program[i + 1] as usize];
-        let param2 = program[program[i + 2] as usize];
-        let output_address = program[i + 3] as usize;
-        program[output_address] = match opcode {
-            OPCODE_ADD => param1 + param2,
-            OPCODE_MULTIPLY => param1 * param2,
-            _ => unreachable!(),
-        }
+// Runs the program and returns its memory, or `None` if it doesn't halt cleanly within the
+// instruction budget.
+fn try_run(program: &[i64]) -> Option<Vec<i64>> {
+    let mut computer = Computer::load(program.to_owned());
+    computer.set_instruction_budget(Some(INSTRUCTION_BUDGET));
+    if !computer.run(vec![]).is_halted() {
+        return None;
     }
+    Some(computer.memory()[..program.len()].to_vec())
+}
+
+fn run(program: &[i64]) -> Vec<i64> {
+    try_run(program).expect("program did not halt")
+}
+
+/// Finds every combination of `values` written to `addresses` that leaves `target` at
+/// address 0, searching on all available cores. Solutions are in ascending order.
+fn find_inputs(
+    program: &[i64],
+    addresses: Range<usize>,
+    values: RangeInclusive<i64>,
+    target: i64,
+) -> Vec<Vec<i64>> {
+    let choices: Vec<i64> = values.collect();
+    let count = choices
+        .len()
+        .checked_pow(addresses.len() as u32)
+        .expect("search space too large");
+    let next = AtomicUsize::new(0);
+    let solutions = Mutex::new(vec![]);
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let mut i = next.fetch_add(1, Ordering::SeqCst);
+                if i >= count {
+                    break;
+                }
+                let mut inputs = vec![0; addresses.len()];
+                for input in inputs.iter_mut().rev() {
+                    *input = choices[i % choices.len()];
+                    i /= choices.len();
+                }
+                let mut patched = program.to_owned();
+                patched[addresses.clone()].copy_from_slice(&inputs);
+                if try_run(&patched).is_some_and(|memory| memory[0] == target) {
+                    solutions.lock().unwrap().push(inputs);
+                }
+            });
+        }
+    });
+    let mut solutions = solutions.into_inner().unwrap();
+    solutions.sort();
+    solutions
 }
 
 #[cfg(test)]
@@ -69,27 +159,21 @@ mod tests {
 
     #[test]
     fn performs_addition() {
-        let mut arr: Vec<u32> = vec![1, 0, 0, 0, 99];
-        run(&mut arr);
-        assert_eq!(arr, vec![2, 0, 0, 0, 99]);
+        assert_eq!(run(&[1, 0, 0, 0, 99]), vec![2, 0, 0, 0, 99]);
     }
 
     #[test]
     fn performs_multiplication() {
-        let mut arr: Vec<u32> = vec![2, 3, 0, 3, 99];
-        run(&mut arr);
-        assert_eq!(arr, vec![2, 3, 0, 6, 99]);
+        assert_eq!(run(&[2, 3, 0, 3, 99]), vec![2, 3, 0, 6, 99]);
     }
 
     #[test]
     fn handles_more_complicated_cases() {
-        let mut arr: Vec<u32> = vec![2, 4, 4, 5, 99, 0];
-        run(&mut arr);
-        assert_eq!(arr, vec![2, 4, 4, 5, 99, 9801]);
-
-        let mut arr: Vec<u32> = vec![1, 1, 1, 4, 99, 5, 6, 0, 99];
-        run(&mut arr);
-        assert_eq!(arr, vec![30, 1, 1, 4, 2, 5, 6, 0, 99]);
+        assert_eq!(run(&[2, 4, 4, 5, 99, 0]), vec![2, 4, 4, 5, 99, 9801]);
+        assert_eq!(
+            run(&[1, 1, 1, 4, 99, 5, 6, 0, 99]),
+            vec![30, 1, 1, 4, 2, 5, 6, 0, 99]
+        );
     }
 
     #[test]
@@ -97,4 +181,43 @@ mod tests {
         let program = read_program("input").unwrap();
         assert_eq!(program[..5], [1, 0, 0, 3, 1]);
     }
+
+    #[test]
+    fn finds_every_solution() {
+        // @0 = @noun + @verb, where @5..@8 hold 1..4
+        let program = vec![1, 0, 0, 0, 99, 1, 2, 3, 4];
+        assert_eq!(
+            find_inputs(&program, 1..3, 5..=8, 5),
+            vec![vec![5, 8], vec![6, 7], vec![7, 6], vec![8, 5]]
+        );
+        assert_eq!(find_inputs(&program, 1..3, 5..=8, 2), vec![vec![5, 5]]);
+        assert!(find_inputs(&program, 1..3, 5..=8, 9).is_empty());
+    }
+
+    #[test]
+    fn skips_patches_that_crash_the_program() {
+        assert_eq!(
+            find_inputs(&[1, 0, 0, 0, 99], 0..1, 0..=99, 2),
+            vec![vec![1]]
+        );
+    }
+
+    #[test]
+    fn skips_patches_that_loop_forever() {
+        assert_eq!(try_run(&[5, 0, 3, 0]), None);
+        // Patching in 1 makes the jump at @0 return to itself.
+        assert_eq!(
+            find_inputs(&[11_05, 0, 0, 99], 1..2, 0..=1, 11_05),
+            vec![vec![0]]
+        );
+    }
+
+    #[test]
+    fn solves_puzzle_target() {
+        let program = read_program("input").unwrap();
+        assert_eq!(
+            find_inputs(&program, 1..3, 0..=99, TARGET),
+            vec![vec![51, 21]]
+        );
+    }
 }
@@ -0,0 +1,289 @@
+use crate::Step;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A straight run of wire. Points along it are reached `steps` steps after the start of the
+/// wire plus their distance from `start`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Segment {
+    pub start: (i32, i32),
+    pub end: (i32, i32),
+    pub steps: u32,
+}
+
+impl Segment {
+    fn is_horizontal(&self) -> bool {
+        self.start.1 == self.end.1
+    }
+
+    // The (fixed, low, high) coordinates of the segment: the y value and x range for a
+    // horizontal segment, otherwise the x value and y range.
+    fn span(&self) -> (i32, i32, i32) {
+        let (a, b) = if self.is_horizontal() {
+            (self.start.0, self.end.0)
+        } else {
+            (self.start.1, self.end.1)
+        };
+        let fixed = if self.is_horizontal() {
+            self.start.1
+        } else {
+            self.start.0
+        };
+        (fixed, a.min(b), a.max(b))
+    }
+
+    fn steps_to(&self, point: (i32, i32)) -> u32 {
+        self.steps + ((point.0 - self.start.0).abs() + (point.1 - self.start.1).abs()) as u32
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Wire {
+    pub segments: Vec<Segment>,
+}
+
+impl Wire {
+    pub fn new(path: &[Step]) -> Self {
+        let mut segments = vec![];
+        let mut pos = (0, 0);
+        let mut steps = 0;
+        for step in path {
+            let d = step.1 as i32;
+            let end = match step.0 {
+                'L' => (pos.0 - d, pos.1),
+                'R' => (pos.0 + d, pos.1),
+                'U' => (pos.0, pos.1 + d),
+                'D' => (pos.0, pos.1 - d),
+                _ => unimplemented!(),
+            };
+            if step.1 > 0 {
+                segments.push(Segment {
+                    start: pos,
+                    end,
+                    steps,
+                });
+            }
+            pos = end;
+            steps += step.1;
+        }
+        Self { segments }
+    }
+}
+
+/// A point, other than the origin, where two wires meet. `steps` gives the fewest steps
+/// each wire takes to reach it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Crossing {
+    pub point: (i32, i32),
+    pub wires: (usize, usize),
+    pub steps: (u32, u32),
+}
+
+// Identifies a segment by (wire, index within wire).
+type SegmentId = (usize, usize);
+
+// Identifies a crossing by the pair of wires and the point.
+type CrossingId = ((usize, usize), (i32, i32));
+
+struct Crossings<'a> {
+    wires: &'a [Wire],
+    found: BTreeMap<CrossingId, (u32, u32)>,
+}
+
+impl Crossings<'_> {
+    fn segment(&self, id: SegmentId) -> &Segment {
+        &self.wires[id.0].segments[id.1]
+    }
+
+    fn record(&mut self, a: SegmentId, b: SegmentId, point: (i32, i32)) {
+        if a.0 == b.0 || point == (0, 0) {
+            return;
+        }
+        let (a, b) = if a.0 < b.0 { (a, b) } else { (b, a) };
+        let steps = (
+            self.segment(a).steps_to(point),
+            self.segment(b).steps_to(point),
+        );
+        let entry = self.found.entry(((a.0, b.0), point)).or_insert(steps);
+        *entry = (entry.0.min(steps.0), entry.1.min(steps.1));
+    }
+
+    // Sweeps a vertical line across the plane. Horizontal segments are active while the
+    // line is over them, and each vertical segment is checked against the active segments
+    // within its y range.
+    fn find_perpendicular(&mut self, horizontal: &[SegmentId], vertical: &[SegmentId]) {
+        const ADD: u8 = 0;
+        const QUERY: u8 = 1;
+        const REMOVE: u8 = 2;
+        let mut events = vec![];
+        for &id in horizontal {
+            let (_, low, high) = self.segment(id).span();
+            events.push((low, ADD, id));
+            events.push((high, REMOVE, id));
+        }
+        for &id in vertical {
+            let (x, _, _) = self.segment(id).span();
+            events.push((x, QUERY, id));
+        }
+        events.sort();
+        let mut active: BTreeMap<i32, BTreeSet<SegmentId>> = BTreeMap::new();
+        for (x, kind, id) in events {
+            let (fixed, low, high) = self.segment(id).span();
+            match kind {
+                ADD => {
+                    active.entry(fixed).or_default().insert(id);
+                }
+                REMOVE => {
+                    let ids = active.get_mut(&fixed).unwrap();
+                    ids.remove(&id);
+                    if ids.is_empty() {
+                        active.remove(&fixed);
+                    }
+                }
+                _ => {
+                    let hits: Vec<(i32, SegmentId)> = active
+                        .range(low..=high)
+                        .flat_map(|(&y, ids)| ids.iter().map(move |&h| (y, h)))
+                        .collect();
+                    for (y, h) in hits {
+                        self.record(id, h, (x, y));
+                    }
+                }
+            }
+        }
+    }
+
+    // Finds overlaps between segments running along the same line.
+    fn find_collinear(&mut self, segments: &[SegmentId], horizontal: bool) {
+        let mut lines: BTreeMap<i32, Vec<(i32, i32, SegmentId)>> = BTreeMap::new();
+        for &id in segments {
+            let (fixed, low, high) = self.segment(id).span();
+            lines.entry(fixed).or_default().push((low, high, id));
+        }
+        for (fixed, mut spans) in lines {
+            spans.sort();
+            for (i, &(_, high, a)) in spans.iter().enumerate() {
+                for &(other_low, other_high, b) in &spans[i + 1..] {
+                    if other_low > high {
+                        break;
+                    }
+                    for v in other_low..=high.min(other_high) {
+                        let point = if horizontal { (v, fixed) } else { (fixed, v) };
+                        self.record(a, b, point);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Finds every point where two different wires meet, ordered by the pair of wires and then
+/// by point.
+pub fn crossings(wires: &[Wire]) -> Vec<Crossing> {
+    let (horizontal, vertical): (Vec<SegmentId>, Vec<SegmentId>) = wires
+        .iter()
+        .enumerate()
+        .flat_map(|(w, wire)| (0..wire.segments.len()).map(move |s| (w, s)))
+        .partition(|&(w, s)| wires[w].segments[s].is_horizontal());
+    let mut crossings = Crossings {
+        wires,
+        found: BTreeMap::new(),
+    };
+    crossings.find_perpendicular(&horizontal, &vertical);
+    crossings.find_collinear(&horizontal, true);
+    crossings.find_collinear(&vertical, false);
+    crossings
+        .found
+        .into_iter()
+        .map(|((wires, point), steps)| Crossing {
+            point,
+            wires,
+            steps,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod geometry_tests {
+    use super::*;
+    use crate::parse_line;
+
+    fn wire(line: &str) -> Wire {
+        Wire::new(&parse_line(line))
+    }
+
+    #[test]
+    fn builds_segments_from_steps() {
+        assert_eq!(
+            wire("R8,U5").segments,
+            vec![
+                Segment {
+                    start: (0, 0),
+                    end: (8, 0),
+                    steps: 0
+                },
+                Segment {
+                    start: (8, 0),
+                    end: (8, 5),
+                    steps: 8
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_crossings_with_steps() {
+        let wires = [wire("R8,U5,L5,D3"), wire("U7,R6,D4,L4")];
+        assert_eq!(
+            crossings(&wires),
+            vec![
+                Crossing {
+                    point: (3, 3),
+                    wires: (0, 1),
+                    steps: (20, 20)
+                },
+                Crossing {
+                    point: (6, 5),
+                    wires: (0, 1),
+                    steps: (15, 15)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_crossings_between_many_wires() {
+        let wires = [wire("R4"), wire("U2,R2,D4"), wire("D1,R3,U3")];
+        let found: Vec<((usize, usize), (i32, i32))> = crossings(&wires)
+            .iter()
+            .map(|c| (c.wires, c.point))
+            .collect();
+        assert_eq!(
+            found,
+            vec![((0, 1), (2, 0)), ((0, 2), (3, 0)), ((1, 2), (2, -1)),]
+        );
+    }
+
+    #[test]
+    fn finds_overlapping_wires() {
+        let wires = [wire("R5"), wire("U1,R2,D1,R4")];
+        let found: Vec<((i32, i32), (u32, u32))> = crossings(&wires)
+            .iter()
+            .map(|c| (c.point, c.steps))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ((2, 0), (2, 4)),
+                ((3, 0), (3, 5)),
+                ((4, 0), (4, 6)),
+                ((5, 0), (5, 7))
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_wires_crossing_themselves() {
+        let wires = [wire("R2,U2,L1,D4"), wire("L3")];
+        assert!(crossings(&wires).is_empty());
+    }
+}
@@ -1,4 +1,7 @@
-use std::collections::{HashMap, HashSet};
+mod geometry;
+mod svg;
+
+use geometry::{crossings, Crossing, Wire};
 use std::fs::File;
 use std::io;
 use std::io::ErrorKind::InvalidData;
@@ -25,6 +28,11 @@ impl FromStr for Step {
 
 fn main() -> io::Result<()> {
     let input = read_input("input")?;
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() == 3 && args[1] == "svg" {
+        let wires: Vec<Wire> = input.iter().map(|path| Wire::new(path)).collect();
+        return std::fs::write(&args[2], svg::render(&wires, &crossings(&wires)));
+    }
     if let Some(distance) = min_distance_for_paths(&input[0], &input[1]) {
         println!("Challenge 1: {}", distance);
     } else {
@@ -39,11 +47,11 @@ fn main() -> io::Result<()> {
 }
 
 fn min_distance_for_paths(path1: &[Step], path2: &[Step]) -> Option<u32> {
-    min_distance(&find_intersections(path1, path2))
+    min_distance(&find_crossings(path1, path2))
 }
 
 fn min_steps_for_paths(path1: &[Step], path2: &[Step]) -> Option<u32> {
-    min_steps(&find_intersections(path1, path2), path1, path2)
+    min_steps(&find_crossings(path1, path2))
 }
 
 fn read_input(filename: &str) -> io::Result<Vec<Vec<Step>>> {
@@ -56,74 +64,20 @@ fn parse_line(line: &str) -> Vec<Step> {
     line.split(',').map(|s| s.parse().unwrap()).collect()
 }
 
-fn find_intersections(path1: &[Step], path2: &[Step]) -> Vec<(i32, i32)> {
-    let mut visited: HashSet<(i32, i32)> = Default::default();
-    walk(path1, |pos| {
-        visited.insert(pos);
-    });
-    let mut intersections = vec![];
-    walk(path2, |pos| {
-        if visited.contains(&pos) {
-            intersections.push(pos);
-        }
-    });
-    intersections
-}
-
-fn walk<F>(path: &[Step], mut func: F)
-where
-    F: FnMut((i32, i32)) -> (),
-{
-    let mut pos = (0, 0);
-    for step in path {
-        let (x, y) = match step.0 {
-            'L' => (-1, 0),
-            'R' => (1, 0),
-            'U' => (0, 1),
-            'D' => (0, -1),
-            _ => unimplemented!(),
-        };
-        for _ in 0..step.1 {
-            pos = (pos.0 + x, pos.1 + y);
-            func(pos);
-        }
-    }
+fn find_crossings(path1: &[Step], path2: &[Step]) -> Vec<Crossing> {
+    crossings(&[Wire::new(path1), Wire::new(path2)])
 }
 
 fn manhattan_distance(loc: (i32, i32)) -> u32 {
     (loc.0.abs() + loc.1.abs()) as u32
 }
 
-fn min_distance(points: &[(i32, i32)]) -> Option<u32> {
-    points.iter().map(|p| manhattan_distance(*p)).min()
+fn min_distance(crossings: &[Crossing]) -> Option<u32> {
+    crossings.iter().map(|c| manhattan_distance(c.point)).min()
 }
 
-fn min_steps(points: &[(i32, i32)], path1: &[Step], path2: &[Step]) -> Option<u32> {
-    if points.is_empty() {
-        return None;
-    }
-    let mut steps: HashMap<(i32, i32), Vec<u32>> = Default::default();
-    let mut path_steps = 0;
-    walk(path1, |pos| {
-        path_steps += 1;
-        if points.contains(&pos) {
-            steps.entry(pos).or_insert_with(|| vec![path_steps]);
-        }
-    });
-    path_steps = 0;
-    walk(path2, |pos| {
-        path_steps += 1;
-        if points.contains(&pos) {
-            let v = steps.get_mut(&pos).unwrap();
-            if v.len() < 2 {
-                v.push(path_steps);
-            }
-        }
-    });
-    steps
-        .iter()
-        .map(|(_, path_steps)| path_steps.iter().sum())
-        .min()
+fn min_steps(crossings: &[Crossing]) -> Option<u32> {
+    crossings.iter().map(|c| c.steps.0 + c.steps.1).min()
 }
 
 #[cfg(test)]
@@ -0,0 +1,125 @@
+use crate::geometry::{Crossing, Wire};
+use crate::manhattan_distance;
+
+const COLOURS: [&str; 6] = [
+    "#e6194b", "#3cb44b", "#4363d8", "#f58231", "#911eb4", "#42d4f4",
+];
+
+// SVG's y axis points down, so points are flipped to keep "U" pointing up.
+fn flip(point: (i32, i32)) -> String {
+    format!("{},{}", point.0, -point.1)
+}
+
+fn circle(class: &str, point: (i32, i32), r: i32, style: &str) -> String {
+    format!(
+        "<circle class=\"{}\" cx=\"{}\" cy=\"{}\" r=\"{}\" {}/>\n",
+        class, point.0, -point.1, r, style
+    )
+}
+
+/// Renders the wires in the order given, the origin and every crossing. The crossing
+/// closest to the origin is ringed in red and the one with the fewest combined steps in
+/// blue; ties go to the first in `crossings`.
+pub fn render(wires: &[Wire], crossings: &[Crossing]) -> String {
+    let points: Vec<(i32, i32)> = wires
+        .iter()
+        .flat_map(|w| w.segments.iter().flat_map(|s| vec![s.start, s.end]))
+        .chain(std::iter::once((0, 0)))
+        .collect();
+    let min_x = points.iter().map(|p| p.0).min().unwrap();
+    let max_x = points.iter().map(|p| p.0).max().unwrap();
+    let min_y = points.iter().map(|p| -p.1).min().unwrap();
+    let max_y = points.iter().map(|p| -p.1).max().unwrap();
+    // Scale line widths and markers so they stay visible on large layouts.
+    let unit = ((max_x - min_x).max(max_y - min_y) / 200).max(1);
+    let margin = 4 * unit;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+        min_x - margin,
+        min_y - margin,
+        max_x - min_x + 2 * margin,
+        max_y - min_y + 2 * margin
+    );
+    svg.push_str(&format!(
+        "<g fill=\"none\" stroke-width=\"{}\" stroke-linejoin=\"round\">\n",
+        unit
+    ));
+    for (i, wire) in wires.iter().enumerate() {
+        if wire.segments.is_empty() {
+            continue;
+        }
+        let mut points = vec![flip(wire.segments[0].start)];
+        points.extend(wire.segments.iter().map(|s| flip(s.end)));
+        svg.push_str(&format!(
+            "<polyline class=\"wire-{}\" stroke=\"{}\" points=\"{}\"/>\n",
+            i,
+            COLOURS[i % COLOURS.len()],
+            points.join(" ")
+        ));
+    }
+    svg.push_str("</g>\n");
+    svg.push_str(&circle("origin", (0, 0), 2 * unit, "fill=\"black\""));
+    for crossing in crossings {
+        svg.push_str(&circle("crossing", crossing.point, unit, "fill=\"black\""));
+    }
+    let ring = |colour: &str| {
+        format!(
+            "fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"",
+            colour, unit
+        )
+    };
+    if let Some(closest) = crossings.iter().min_by_key(|c| manhattan_distance(c.point)) {
+        svg.push_str(&circle("closest", closest.point, 3 * unit, &ring("red")));
+    }
+    if let Some(fewest) = crossings.iter().min_by_key(|c| c.steps.0 + c.steps.1) {
+        svg.push_str(&circle(
+            "fewest-steps",
+            fewest.point,
+            4 * unit,
+            &ring("blue"),
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod svg_tests {
+    use super::*;
+    use crate::geometry::crossings;
+    use crate::parse_line;
+
+    #[test]
+    fn renders_wires_and_crossings() {
+        let wires = [
+            Wire::new(&parse_line("R8,U5,L5,D3")),
+            Wire::new(&parse_line("U7,R6,D4,L4")),
+        ];
+        let svg = render(&wires, &crossings(&wires));
+        assert_eq!(
+            svg,
+            r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-4 -11 16 15">
+<g fill="none" stroke-width="1" stroke-linejoin="round">
+<polyline class="wire-0" stroke="#e6194b" points="0,0 8,0 8,-5 3,-5 3,-2"/>
+<polyline class="wire-1" stroke="#3cb44b" points="0,0 0,-7 6,-7 6,-3 2,-3"/>
+</g>
+<circle class="origin" cx="0" cy="0" r="2" fill="black"/>
+<circle class="crossing" cx="3" cy="-3" r="1" fill="black"/>
+<circle class="crossing" cx="6" cy="-5" r="1" fill="black"/>
+<circle class="closest" cx="3" cy="-3" r="3" fill="none" stroke="red" stroke-width="1"/>
+<circle class="fewest-steps" cx="6" cy="-5" r="4" fill="none" stroke="blue" stroke-width="1"/>
+</svg>
+"##
+        );
+    }
+
+    #[test]
+    fn renders_wires_without_crossings() {
+        let wires = [Wire::new(&parse_line("R2")), Wire::new(&parse_line("L2"))];
+        let svg = render(&wires, &crossings(&wires));
+        assert!(svg.contains("class=\"origin\""));
+        assert!(!svg.contains("class=\"crossing\""));
+        assert!(!svg.contains("class=\"closest\""));
+    }
+}
@@ -1,69 +1,105 @@
 #![allow(clippy::unreadable_literal)]
 
-const TEN: u32 = 10;
+mod password;
 
-fn main() {
-    println!("Challenge 1: {}", valid_count(359282, 820401, check1));
-    println!("Challenge 2: {}", valid_count(359282, 820401, check2));
-}
+use password::{Counter, Length, NonDecreasing, RunOfAtLeast, RunOfExactly};
+use std::env;
 
-fn valid_count<F>(start: u32, end: u32, check_func: F) -> u32
-where
-    F: Fn(u32) -> bool,
-{
-    let mut count = 0;
-    for n in start..=end {
-        if check_func(n) {
-            count += 1;
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(|s| s.as_str()) {
+        None => {
+            println!("Challenge 1: {}", valid_count(359282, 820401, &rules1()));
+            println!("Challenge 2: {}", valid_count(359282, 820401, &rules2()));
+        }
+        Some("count") if args.len() >= 4 => {
+            let (start, end, counter) = parse_query(&args[2..]);
+            println!("{}", valid_count(start, end, &counter));
+        }
+        Some("list") if args.len() >= 4 => {
+            let (start, end, counter) = parse_query(&args[2..]);
+            for n in counter.matches(start, end) {
+                println!("{}", n);
+            }
+        }
+        Some("sample") if args.len() >= 6 => {
+            let n = args[2].parse().unwrap_or_else(|_| usage());
+            let seed = args[3].parse().unwrap_or_else(|_| usage());
+            let (start, end, counter) = parse_query(&args[4..]);
+            for n in counter.sample(start, end, n, seed) {
+                println!("{}", n);
+            }
         }
+        _ => usage(),
     }
-    count
 }
 
-fn check1(n: u32) -> bool {
-    if format!("{}", n).len() != 6 {
-        return false;
-    }
-    let mut last_digit = n / TEN.pow(5);
-    let mut found_adj = false;
-    for p in (0..=4).rev() {
-        let digit = (n % TEN.pow(p + 1)) / TEN.pow(p);
-        if digit < last_digit {
-            return false;
-        }
-        found_adj |= digit == last_digit;
-        last_digit = digit;
-    }
-    found_adj
+fn usage() -> ! {
+    let program = env::args().next().unwrap();
+    println!("Usage:");
+    println!("    {}", program);
+    println!("    {} count START END [RULE...]", program);
+    println!("    {} list START END [RULE...]", program);
+    println!("    {} sample N SEED START END [RULE...]", program);
+    println!();
+    println!("Arguments:");
+    println!(
+        "    start, end: inclusive range of passwords, up to {}",
+        u128::MAX
+    );
+    println!("    rule: non-decreasing, run=K, run>=K, sum=S or length=N");
+    println!("    n: number of passwords to pick at random");
+    println!("    seed: number to seed the random generator with");
+    std::process::exit(1);
 }
 
-fn check2(n: u32) -> bool {
-    if format!("{}", n).len() != 6 {
-        return false;
-    }
-    let mut last_digit = n / TEN.pow(5);
-    let mut consecutive = 1;
-    let mut found_two = false;
-    for p in (0..=4).rev() {
-        let digit = (n % TEN.pow(p + 1)) / TEN.pow(p);
-        if digit < last_digit {
-            return false;
-        }
-        if digit == last_digit {
-            consecutive += 1;
-            continue;
-        }
-        found_two |= consecutive == 2;
-        last_digit = digit;
-        consecutive = 1;
-    }
-    found_two | (consecutive == 2)
+fn parse_query(args: &[String]) -> (u128, u128, Counter) {
+    let start = args[0].parse().unwrap_or_else(|_| usage());
+    let end = args[1].parse().unwrap_or_else(|_| usage());
+    let rules = args[2..]
+        .iter()
+        .map(|r| {
+            password::parse_rule(r).unwrap_or_else(|| {
+                eprintln!("unknown rule {}", r);
+                usage();
+            })
+        })
+        .collect();
+    (start, end, Counter::new(rules))
+}
+
+fn rules1() -> Counter {
+    Counter::new(vec![
+        Box::new(Length(6)),
+        Box::new(NonDecreasing),
+        Box::new(RunOfAtLeast(2)),
+    ])
+}
+
+fn rules2() -> Counter {
+    Counter::new(vec![
+        Box::new(Length(6)),
+        Box::new(NonDecreasing),
+        Box::new(RunOfExactly(2)),
+    ])
+}
+
+fn valid_count(start: u128, end: u128, rules: &Counter) -> u128 {
+    rules.count(start, end)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn check1(n: u128) -> bool {
+        rules1().is_match(n)
+    }
+
+    fn check2(n: u128) -> bool {
+        rules2().is_match(n)
+    }
+
     #[test]
     fn check1_requires_6_digits() {
         assert!(!check1(11345));
@@ -139,6 +175,6 @@ mod tests {
 
     #[test]
     fn calculates_valid_count() {
-        assert_eq!(valid_count(123450, 123470, check1), 2);
+        assert_eq!(valid_count(123450, 123470, &rules1()), 2);
     }
 }
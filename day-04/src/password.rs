@@ -0,0 +1,445 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// A check applied to the digits of a password one at a time, most significant first. Each
+/// rule keeps whatever it needs to remember about the digits seen so far in a `u64` state.
+pub trait Rule {
+    fn start(&self) -> u64;
+
+    /// Returns `None` if no password starting with these digits can match.
+    fn step(&self, state: u64, digit: u8) -> Option<u64>;
+
+    fn accepts(&self, state: u64) -> bool;
+}
+
+const NO_DIGIT: u64 = 10;
+
+// Packs the state of the run rules: the last digit, the length of the current run and
+// whether a qualifying run has already been seen.
+fn pack(last: u64, run: u64, found: bool) -> u64 {
+    last | run << 4 | (found as u64) << 63
+}
+
+fn unpack(state: u64) -> (u64, u64, bool) {
+    (state & 0xf, (state >> 4) & !(1 << 59), state >> 63 == 1)
+}
+
+/// Digits never decrease from left to right.
+pub struct NonDecreasing;
+
+impl Rule for NonDecreasing {
+    fn start(&self) -> u64 {
+        NO_DIGIT
+    }
+
+    fn step(&self, last: u64, digit: u8) -> Option<u64> {
+        if last != NO_DIGIT && u64::from(digit) < last {
+            None
+        } else {
+            Some(u64::from(digit))
+        }
+    }
+
+    fn accepts(&self, _state: u64) -> bool {
+        true
+    }
+}
+
+/// Some digit is repeated exactly this many times in a row.
+pub struct RunOfExactly(pub u64);
+
+impl Rule for RunOfExactly {
+    fn start(&self) -> u64 {
+        pack(NO_DIGIT, 0, false)
+    }
+
+    fn step(&self, state: u64, digit: u8) -> Option<u64> {
+        let (last, run, found) = unpack(state);
+        let digit = u64::from(digit);
+        Some(if digit == last {
+            pack(last, (run + 1).min(self.0 + 1), found)
+        } else {
+            pack(digit, 1, found || run == self.0)
+        })
+    }
+
+    fn accepts(&self, state: u64) -> bool {
+        let (_, run, found) = unpack(state);
+        found || run == self.0
+    }
+}
+
+/// Some digit is repeated at least this many times in a row.
+pub struct RunOfAtLeast(pub u64);
+
+impl Rule for RunOfAtLeast {
+    fn start(&self) -> u64 {
+        pack(NO_DIGIT, 0, false)
+    }
+
+    fn step(&self, state: u64, digit: u8) -> Option<u64> {
+        let (last, run, found) = unpack(state);
+        let digit = u64::from(digit);
+        let run = if digit == last { run + 1 } else { 1 }.min(self.0);
+        Some(pack(digit, run, found || run >= self.0))
+    }
+
+    fn accepts(&self, state: u64) -> bool {
+        unpack(state).2
+    }
+}
+
+/// The password has exactly this many digits.
+pub struct Length(pub u64);
+
+impl Rule for Length {
+    fn start(&self) -> u64 {
+        0
+    }
+
+    fn step(&self, len: u64, _digit: u8) -> Option<u64> {
+        Some(len + 1).filter(|&len| len <= self.0)
+    }
+
+    fn accepts(&self, len: u64) -> bool {
+        len == self.0
+    }
+}
+
+/// The digits add up to this value.
+pub struct DigitSum(pub u64);
+
+impl Rule for DigitSum {
+    fn start(&self) -> u64 {
+        0
+    }
+
+    fn step(&self, sum: u64, digit: u8) -> Option<u64> {
+        Some(sum + u64::from(digit)).filter(|&sum| sum <= self.0)
+    }
+
+    fn accepts(&self, sum: u64) -> bool {
+        sum == self.0
+    }
+}
+
+/// Parses a rule written as `non-decreasing`, `run=K`, `run>=K`, `sum=S` or `length=N`.
+pub fn parse_rule(text: &str) -> Option<Box<dyn Rule>> {
+    if text == "non-decreasing" {
+        return Some(Box::new(NonDecreasing));
+    }
+    let (name, value) = if let Some(value) = text.strip_prefix("run>=") {
+        ("run>=", value)
+    } else {
+        let mut parts = text.splitn(2, '=');
+        (parts.next()?, parts.next()?)
+    };
+    let value = value.parse().ok()?;
+    Some(match name {
+        "run" => Box::new(RunOfExactly(value)),
+        "run>=" => Box::new(RunOfAtLeast(value)),
+        "sum" => Box::new(DigitSum(value)),
+        "length" => Box::new(Length(value)),
+        _ => return None,
+    })
+}
+
+fn digits(n: u128) -> Vec<u8> {
+    n.to_string().bytes().map(|b| b - b'0').collect()
+}
+
+/// Counts, enumerates and samples the numbers in a range that pass every rule, without
+/// visiting each number in the range.
+pub struct Counter {
+    rules: Vec<Box<dyn Rule>>,
+    // The number of ways to finish a password with this many digits left from these states.
+    completions: RefCell<HashMap<(usize, Vec<u64>), u128>>,
+}
+
+impl Counter {
+    pub fn new(rules: Vec<Box<dyn Rule>>) -> Self {
+        Self {
+            rules,
+            completions: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn start(&self) -> Vec<u64> {
+        self.rules.iter().map(|r| r.start()).collect()
+    }
+
+    fn step(&self, states: &[u64], digit: u8) -> Option<Vec<u64>> {
+        self.rules
+            .iter()
+            .zip(states)
+            .map(|(r, &s)| r.step(s, digit))
+            .collect()
+    }
+
+    fn accepts(&self, states: &[u64]) -> bool {
+        self.rules.iter().zip(states).all(|(r, &s)| r.accepts(s))
+    }
+
+    pub fn is_match(&self, n: u128) -> bool {
+        digits(n)
+            .into_iter()
+            .try_fold(self.start(), |states, d| self.step(&states, d))
+            .is_some_and(|states| self.accepts(&states))
+    }
+
+    fn completions(&self, remaining: usize, states: &[u64]) -> u128 {
+        if remaining == 0 {
+            return self.accepts(states) as u128;
+        }
+        let key = (remaining, states.to_vec());
+        if let Some(&count) = self.completions.borrow().get(&key) {
+            return count;
+        }
+        let count = (0..10)
+            .filter_map(|d| self.step(states, d))
+            .map(|next| self.completions(remaining - 1, &next))
+            .sum();
+        self.completions.borrow_mut().insert(key, count);
+        count
+    }
+
+    // Counts the ways to finish a password from `states` with digits no greater than `bound`
+    // when read as a number.
+    fn count_bounded(&self, states: Vec<u64>, bound: &[u8]) -> u128 {
+        let mut states = states;
+        let mut count = 0;
+        for (i, &b) in bound.iter().enumerate() {
+            for d in 0..b {
+                if let Some(next) = self.step(&states, d) {
+                    count += self.completions(bound.len() - i - 1, &next);
+                }
+            }
+            states = match self.step(&states, b) {
+                Some(next) => next,
+                None => return count,
+            };
+        }
+        count + self.accepts(&states) as u128
+    }
+
+    // Counts the matches of `len` digits, no greater than `bound` if given.
+    fn count_of_length(&self, len: usize, bound: Option<&[u8]>) -> u128 {
+        let high = bound.map_or(9, |b| b[0]);
+        (1..=high)
+            .filter_map(|d| self.step(&self.start(), d).map(|next| (d, next)))
+            .map(|(d, next)| match bound {
+                Some(bound) if d == high => self.count_bounded(next, &bound[1..]),
+                _ => self.completions(len - 1, &next),
+            })
+            .sum()
+    }
+
+    // Counts the matches in 0..=n.
+    fn count_to(&self, n: u128) -> u128 {
+        let digits = digits(n);
+        let zero = self.is_match(0) as u128;
+        if n == 0 {
+            return zero;
+        }
+        let shorter: u128 = (1..digits.len())
+            .map(|len| self.count_of_length(len, None))
+            .sum();
+        zero + shorter + self.count_of_length(digits.len(), Some(&digits))
+    }
+
+    fn count_before(&self, start: u128) -> u128 {
+        if start == 0 {
+            0
+        } else {
+            self.count_to(start - 1)
+        }
+    }
+
+    pub fn count(&self, start: u128, end: u128) -> u128 {
+        if start > end {
+            return 0;
+        }
+        self.count_to(end) - self.count_before(start)
+    }
+
+    // Finds the `k`th (from 0) match of `len` digits, no greater than `bound` if given, or
+    // returns `k` less the number of such matches.
+    fn nth_of_length(&self, len: usize, bound: Option<&[u8]>, mut k: u128) -> Result<u128, u128> {
+        let mut states = self.start();
+        let mut value = 0;
+        let mut tight = bound.is_some();
+        'digits: for i in 0..len {
+            let low = if i == 0 { 1 } else { 0 };
+            let high = if tight { bound.unwrap()[i] } else { 9 };
+            for d in low..=high {
+                let next = match self.step(&states, d) {
+                    Some(next) => next,
+                    None => continue,
+                };
+                let count = if tight && d == high {
+                    self.count_bounded(next.clone(), &bound.unwrap()[i + 1..])
+                } else {
+                    self.completions(len - i - 1, &next)
+                };
+                if k < count {
+                    value = value * 10 + u128::from(d);
+                    states = next;
+                    tight = tight && d == high;
+                    continue 'digits;
+                }
+                k -= count;
+            }
+            return Err(k);
+        }
+        Ok(value)
+    }
+
+    // Finds the `k`th (from 0) match in 0..=end, which must exist.
+    fn nth_to(&self, end: u128, mut k: u128) -> u128 {
+        if self.is_match(0) {
+            if k == 0 {
+                return 0;
+            }
+            k -= 1;
+        }
+        let digits = digits(end);
+        for len in 1..=digits.len() {
+            let bound = if len == digits.len() {
+                Some(&digits[..])
+            } else {
+                None
+            };
+            match self.nth_of_length(len, bound, k) {
+                Ok(value) => return value,
+                Err(rest) => k = rest,
+            }
+        }
+        unreachable!()
+    }
+
+    /// Finds the `k`th (from 0) match in `start..=end`.
+    pub fn nth(&self, start: u128, end: u128, k: u128) -> Option<u128> {
+        if k >= self.count(start, end) {
+            return None;
+        }
+        Some(self.nth_to(end, self.count_before(start) + k))
+    }
+
+    /// Iterates over the matches in `start..=end` in ascending order.
+    pub fn matches(&self, start: u128, end: u128) -> impl Iterator<Item = u128> + '_ {
+        let before = self.count_before(start);
+        (0..self.count(start, end)).map(move |k| self.nth_to(end, before + k))
+    }
+
+    /// Picks `n` matches in `start..=end` uniformly at random, with replacement, from a
+    /// generator seeded with `seed` so that samples can be reproduced.
+    pub fn sample(&self, start: u128, end: u128, n: usize, seed: u64) -> Vec<u128> {
+        let count = self.count(start, end);
+        if count == 0 {
+            return vec![];
+        }
+        // A splitmix64 step spreads the seed over the xorshift state, which must not be zero.
+        let mut rng = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        rng = (rng ^ rng >> 30).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        rng = (rng ^ rng >> 27).wrapping_mul(0x94d0_49bb_1331_11eb);
+        rng ^= rng >> 31;
+        if rng == 0 {
+            rng = 0x9e37_79b9_7f4a_7c15;
+        }
+        let mut next = || {
+            let mut x: u128 = 0;
+            for _ in 0..2 {
+                rng ^= rng << 13;
+                rng ^= rng >> 7;
+                rng ^= rng << 17;
+                x = x << 64 | u128::from(rng);
+            }
+            x
+        };
+        (0..n)
+            .map(|_| self.nth(start, end, next() % count).unwrap())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod password_tests {
+    use super::*;
+
+    fn brute_force(counter: &Counter, start: u128, end: u128) -> Vec<u128> {
+        (start..=end).filter(|&n| counter.is_match(n)).collect()
+    }
+
+    #[test]
+    fn applies_rules_to_digits() {
+        let counter = Counter::new(vec![Box::new(NonDecreasing), Box::new(RunOfExactly(2))]);
+        assert!(counter.is_match(112233));
+        assert!(!counter.is_match(123444));
+        assert!(counter.is_match(111122));
+        assert!(!counter.is_match(223450));
+
+        let counter = Counter::new(vec![Box::new(RunOfAtLeast(3)), Box::new(DigitSum(9))]);
+        assert!(counter.is_match(3330));
+        assert!(!counter.is_match(3303));
+        assert!(!counter.is_match(4440));
+    }
+
+    #[test]
+    fn parses_rules() {
+        let rules = ["length=4", "non-decreasing", "run>=3", "sum=6"];
+        let counter = Counter::new(rules.iter().map(|r| parse_rule(r).unwrap()).collect());
+        assert_eq!(counter.matches(0, 9999).collect::<Vec<_>>(), vec![1113]);
+        assert!(counter.is_match(1113));
+        assert!(parse_rule("run=2").is_some());
+        assert!(parse_rule("run>2").is_none());
+        assert!(parse_rule("sum=x").is_none());
+    }
+
+    #[test]
+    fn counts_like_brute_force() {
+        let rule_sets: [&[&str]; 6] = [
+            &["non-decreasing", "run>=2"],
+            &["non-decreasing", "run=2"],
+            &["run>=3"],
+            &["sum=10", "run=1"],
+            &["length=3", "run>=2"],
+            &[],
+        ];
+        for rules in rule_sets {
+            let counter = Counter::new(rules.iter().map(|r| parse_rule(r).unwrap()).collect());
+            for &(start, end) in &[(0, 0), (0, 9), (5, 123), (98, 10_000), (4321, 12_345)] {
+                let expected = brute_force(&counter, start, end);
+                assert_eq!(counter.count(start, end), expected.len() as u128);
+                assert_eq!(counter.matches(start, end).collect::<Vec<_>>(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn counts_huge_ranges() {
+        let counter = Counter::new(vec![Box::new(NonDecreasing)]);
+        // Non-decreasing strings of 38 digits from 1-9, plus those of every shorter length.
+        let expected: u128 = (1..=38).map(|len| binomial(len + 8, 8)).sum();
+        assert_eq!(counter.count(1, 10u128.pow(38) - 1), expected);
+        let last = counter.nth(1, u128::MAX, expected - 1).unwrap();
+        assert_eq!(last, 99_999_999_999_999_999_999_999_999_999_999_999_999);
+    }
+
+    fn binomial(n: u128, k: u128) -> u128 {
+        (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+    }
+
+    #[test]
+    fn samples_matches_reproducibly() {
+        let counter = Counter::new(vec![Box::new(NonDecreasing), Box::new(RunOfExactly(2))]);
+        let sample = counter.sample(359_282, 820_401, 20, 7);
+        assert_eq!(sample.len(), 20);
+        assert!(sample
+            .iter()
+            .all(|&n| (359_282..=820_401).contains(&n) && counter.is_match(n)));
+        assert_eq!(sample, counter.sample(359_282, 820_401, 20, 7));
+        assert_ne!(sample, counter.sample(359_282, 820_401, 20, 8));
+        assert_ne!(sample, counter.sample(359_282, 820_401, 20, 6));
+        assert!(counter.sample(5, 9, 3, 1).is_empty());
+    }
+}
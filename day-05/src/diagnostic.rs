@@ -0,0 +1,171 @@
+use crate::executor::{Executor, Operand, Step, Trace};
+use std::fmt;
+
+/// A test that output something other than 0, with the instructions that last wrote each of
+/// the values the output instruction read.
+#[derive(Debug)]
+pub struct Failure {
+    pub test: usize,
+    pub value: i32,
+    pub step: Step,
+    pub writers: Vec<Option<Step>>,
+}
+
+/// The result of running the TEST program: every output but the last should be 0 and the
+/// last is the diagnostic code.
+#[derive(Debug)]
+pub struct Report {
+    pub system_id: i32,
+    pub tests: usize,
+    pub failures: Vec<Failure>,
+    pub code: Option<i32>,
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty() && self.code.is_some()
+    }
+}
+
+pub fn diagnose(program: Vec<i32>, system_id: i32) -> Report {
+    let trace = Executor::trace(program, vec![system_id]);
+    report(system_id, &trace)
+}
+
+fn report(system_id: i32, trace: &Trace) -> Report {
+    let outputs = trace.output_steps();
+    let (code, tests) = match outputs.split_last() {
+        Some((code, tests)) => (code.output, tests),
+        None => (None, &[][..]),
+    };
+    let failures = tests
+        .iter()
+        .enumerate()
+        .filter(|(_, step)| step.output != Some(0))
+        .map(|(i, step)| Failure {
+            test: i + 1,
+            value: step.output.unwrap(),
+            step: (*step).clone(),
+            writers: step
+                .inputs
+                .iter()
+                .map(|input| input.written_by.map(|s| trace.steps[s].clone()))
+                .collect(),
+        })
+        .collect();
+    Report {
+        system_id,
+        tests: tests.len(),
+        failures,
+        code,
+    }
+}
+
+fn describe(operand: &Operand) -> String {
+    match operand.source {
+        Some(addr) => format!("@{} = {}", addr, operand.value),
+        None => format!("immediate {}", operand.value),
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "System {}: {} of {} tests failed",
+            self.system_id,
+            self.failures.len(),
+            self.tests
+        )?;
+        for failure in &self.failures {
+            writeln!(
+                f,
+                "test {} output {} from {:?} at {}",
+                failure.test, failure.value, failure.step.opcode, failure.step.addr
+            )?;
+            for (input, writer) in failure.step.inputs.iter().zip(&failure.writers) {
+                match (input.source, writer) {
+                    (None, _) => writeln!(f, "    read {}", describe(input))?,
+                    (Some(_), None) => {
+                        writeln!(f, "    read {}, unchanged from program", describe(input))?
+                    }
+                    (Some(_), Some(writer)) => {
+                        let inputs: Vec<String> = writer.inputs.iter().map(describe).collect();
+                        writeln!(
+                            f,
+                            "    read {}, last written by {:?} at {} from {}",
+                            describe(input),
+                            writer.opcode,
+                            writer.addr,
+                            inputs.join(", ")
+                        )?
+                    }
+                }
+            }
+        }
+        match self.code {
+            Some(code) => writeln!(f, "Diagnostic code = {}", code),
+            None => writeln!(f, "No diagnostic code"),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::inconsistent_digit_grouping)]
+mod diagnostic_tests {
+    use super::*;
+    use crate::read_program;
+
+    #[test]
+    fn passes_day_5_program() {
+        let report = diagnose(read_program("input").unwrap(), 1);
+        assert!(report.passed());
+        assert!(report.tests > 0);
+        assert_eq!(report.code, Some(15259545));
+    }
+
+    #[test]
+    fn reports_failing_tests() {
+        let program: Vec<i32> = vec![
+            1_04,  // 0: output
+            0,     // 1: 0
+            3,     // 2: input
+            17,    // 3: ... to @17
+            10_02, // 4: multiply
+            17,    // 5: @17
+            3,     // 6: 3
+            18,    // 7: ... to @18
+            4,     // 8: output
+            18,    // 9: @18
+            4,     // 10: output
+            19,    // 11: @19
+            1_04,  // 12: output
+            42,    // 13: 42
+            99,    // 14: halt
+            0,     // 15
+            0,     // 16
+            0,     // 17
+            0,     // 18
+            -1,    // 19
+        ];
+        let report = diagnose(program, 2);
+        assert!(!report.passed());
+        assert_eq!((report.tests, report.code), (3, Some(42)));
+        let failures: Vec<(usize, i32, usize)> = report
+            .failures
+            .iter()
+            .map(|f| (f.test, f.value, f.step.addr))
+            .collect();
+        assert_eq!(failures, vec![(2, 6, 8), (3, -1, 10)]);
+        assert_eq!(
+            report.to_string(),
+            "System 2: 2 of 3 tests failed
+test 2 output 6 from Output at 8
+    read @18 = 6, last written by Multiply at 4 from @17 = 2, immediate 3
+test 3 output -1 from Output at 10
+    read @19 = -1, unchanged from program
+Diagnostic code = 42
+"
+        );
+    }
+}
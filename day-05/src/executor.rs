@@ -1,9 +1,9 @@
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 
-#[derive(FromPrimitive, Debug)]
-enum Opcode {
+#[derive(FromPrimitive, Debug, Clone, Copy, PartialEq)]
+pub enum Opcode {
     Halt = 99,
     Add = 1,
     Multiply = 2,
@@ -33,20 +33,69 @@ impl ModeGenerator {
     }
 }
 
+/// A value read by an instruction. `source` is the address it was read from, or `None` for
+/// an immediate value, and `written_by` is the step that last wrote to that address, or
+/// `None` if it still holds its value from the program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operand {
+    pub value: i32,
+    pub source: Option<usize>,
+    pub written_by: Option<usize>,
+}
+
+/// One executed instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub addr: usize,
+    pub opcode: Opcode,
+    pub inputs: Vec<Operand>,
+    pub write: Option<(usize, i32)>,
+    pub output: Option<i32>,
+}
+
+#[derive(Debug)]
+pub struct Trace {
+    pub output: Vec<i32>,
+    pub steps: Vec<Step>,
+}
+
+impl Trace {
+    /// The steps that produced each output, in order.
+    pub fn output_steps(&self) -> Vec<&Step> {
+        self.steps.iter().filter(|s| s.output.is_some()).collect()
+    }
+}
+
 pub struct Executor {
     program: Vec<i32>,
     input: VecDeque<i32>,
     output: Vec<i32>,
     pos: usize,
+    // Without tracing only the step in progress is kept.
+    tracing: bool,
+    steps: Vec<Step>,
+    last_writes: HashMap<usize, usize>,
 }
 
 impl Executor {
     pub fn run(program: Vec<i32>, input: Vec<i32>) -> Vec<i32> {
+        Self::execute(program, input, false).output
+    }
+
+    /// Runs the program, recording every instruction executed and where its inputs came from.
+    pub fn trace(program: Vec<i32>, input: Vec<i32>) -> Trace {
+        Self::execute(program, input, true)
+    }
+
+    fn execute(program: Vec<i32>, input: Vec<i32>, tracing: bool) -> Trace {
         let mut executor = Self {
             program,
             input: VecDeque::from(input),
             pos: 0,
             output: Vec::new(),
+            tracing,
+            steps: Vec::new(),
+            last_writes: HashMap::new(),
         };
         loop {
             match executor.read_opcode() {
@@ -61,7 +110,22 @@ impl Executor {
                 (Opcode::Equals, mg) => executor.op_equals(mg),
             }
         }
-        executor.output
+        Trace {
+            output: executor.output,
+            steps: executor.steps,
+        }
+    }
+
+    fn step(&mut self) -> &mut Step {
+        self.steps.last_mut().unwrap()
+    }
+
+    fn write(&mut self, addr: usize, value: i32) {
+        self.program[addr] = value;
+        if self.tracing {
+            self.last_writes.insert(addr, self.steps.len() - 1);
+        }
+        self.step().write = Some((addr, value));
     }
 
     fn read(&mut self) -> i32 {
@@ -71,30 +135,54 @@ impl Executor {
     }
 
     fn read_opcode(&mut self) -> (Opcode, ModeGenerator) {
+        let addr = self.pos;
         let val = self.read();
         let opcode = val % 100;
         let mode_gen = ModeGenerator { val: val / 100 };
         let opcode = FromPrimitive::from_i32(opcode)
             .unwrap_or_else(|| panic!("unexpected opcode: {}", opcode));
+        if !self.tracing {
+            self.steps.clear();
+        }
+        self.steps.push(Step {
+            addr,
+            opcode,
+            inputs: vec![],
+            write: None,
+            output: None,
+        });
         (opcode, mode_gen)
     }
 
     fn read_param(&mut self, mode_gen: &mut ModeGenerator) -> i32 {
-        let mut v = self.read();
-        if let Mode::Position = mode_gen.next() {
-            v = self.program[v as usize];
-        }
+        let v = self.read();
+        let operand = match mode_gen.next() {
+            Mode::Position => Operand {
+                value: self.program[v as usize],
+                source: Some(v as usize),
+                written_by: self.last_writes.get(&(v as usize)).copied(),
+            },
+            Mode::Immediate => Operand {
+                value: v,
+                source: None,
+                written_by: None,
+            },
+        };
+        let v = operand.value;
+        self.step().inputs.push(operand);
         v
     }
 
     fn op_input(&mut self) {
         let out_addr = self.read() as usize;
-        self.program[out_addr] = self.input.pop_front().expect("insufficient input values");
+        let value = self.input.pop_front().expect("insufficient input values");
+        self.write(out_addr, value);
     }
 
     fn op_output(&mut self, mut mode_gen: ModeGenerator) {
         let v = self.read_param(&mut mode_gen);
-        self.output.push(v)
+        self.output.push(v);
+        self.step().output = Some(v);
     }
 
     fn op_jump_if_true(&mut self, mode_gen: ModeGenerator) {
@@ -131,7 +219,7 @@ impl Executor {
         let v1 = self.read_param(&mut mode_gen);
         let v2 = self.read_param(&mut mode_gen);
         let dest = self.read() as usize;
-        self.program[dest] = if test(v1, v2) { 1 } else { 0 };
+        self.write(dest, if test(v1, v2) { 1 } else { 0 });
     }
 
     fn op_add(&mut self, mut mode_gen: ModeGenerator) {
@@ -149,11 +237,12 @@ impl Executor {
         let param1 = self.read_param(mode_gen);
         let param2 = self.read_param(mode_gen);
         let output_address = self.read() as usize;
-        self.program[output_address] = op(param1, param2);
+        self.write(output_address, op(param1, param2));
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::inconsistent_digit_grouping)]
 mod executor_tests {
     use super::*;
 
@@ -385,6 +474,68 @@ mod executor_tests {
         assert_eq!(out, vec![0]);
     }
 
+    #[test]
+    fn traces_where_inputs_were_written() {
+        let program: Vec<i32> = vec![
+            3,     // 0: input
+            9,     // 1: ... to @9
+            10_01, // 2: add
+            9,     // 3: @9
+            5,     // 4: 5
+            10,    // 5: ... to @10
+            4,     // 6: output
+            10,    // 7: @10
+            99,    // 8: halt
+            0,     // 9
+            0,     // 10
+        ];
+        let trace = Executor::trace(program, vec![2]);
+        assert_eq!(trace.output, vec![7]);
+        assert_eq!(trace.steps.len(), 4);
+        assert_eq!(trace.steps[0].write, Some((9, 2)));
+        assert_eq!(
+            trace.steps[1].inputs,
+            vec![
+                Operand {
+                    value: 2,
+                    source: Some(9),
+                    written_by: Some(0)
+                },
+                Operand {
+                    value: 5,
+                    source: None,
+                    written_by: None
+                },
+            ]
+        );
+        let output = trace.output_steps()[0];
+        assert_eq!((output.addr, output.opcode), (6, Opcode::Output));
+        assert_eq!(output.inputs[0].written_by, Some(1));
+    }
+
+    #[test]
+    fn keeps_no_history_without_tracing() {
+        // Counts down from 1000, outputting 0.
+        let program: Vec<i32> = vec![
+            1001, // 0: add
+            10,   // 1: @10
+            -1,   // 2: -1
+            10,   // 3: ... to @10
+            1005, // 4: jump if true
+            10,   // 5: @10
+            0,    // 6: to @0
+            104,  // 7: output
+            0,    // 8: 0
+            99,   // 9: halt
+            1000, // 10
+        ];
+        let trace = Executor::execute(program.clone(), vec![], false);
+        assert_eq!(trace.output, vec![0]);
+        assert_eq!(trace.steps.len(), 1);
+        assert!(trace.steps[0].inputs.iter().all(|i| i.written_by.is_none()));
+        assert_eq!(Executor::trace(program, vec![]).steps.len(), 2002);
+    }
+
     #[test]
     fn examples() {
         let program: Vec<i32> = vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
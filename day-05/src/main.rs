@@ -1,22 +1,51 @@
+mod diagnostic;
 mod executor;
 
 use executor::Executor;
+use std::env;
 use std::fs::File;
 use std::io::{Read, Result};
 
 fn main() -> Result<()> {
-    let program = read_program("input")?;
-    challenge_1(program.clone());
-    challenge_2(program);
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(|s| s.as_str()) {
+        None => {
+            let program = read_program("input")?;
+            challenge_1(program.clone());
+            challenge_2(program);
+        }
+        Some("diagnose") if args.len() == 3 || args.len() == 4 => {
+            let system_id = args[2].parse().unwrap_or_else(|_| usage());
+            let program = read_program(args.get(3).map_or("input", |s| s.as_str()))?;
+            let report = diagnostic::diagnose(program, system_id);
+            print!("{}", report);
+            if !report.passed() {
+                std::process::exit(1);
+            }
+        }
+        _ => usage(),
+    }
     Ok(())
 }
 
+fn usage() -> ! {
+    let program = env::args().next().unwrap();
+    println!("Usage:");
+    println!("    {}", program);
+    println!("    {} diagnose SYSTEM_ID [PROGRAM]", program);
+    println!();
+    println!("Arguments:");
+    println!("    system_id: ID of the system to test, e.g. 1 for the air conditioner");
+    println!("    program: file containing the TEST program (default input)");
+    std::process::exit(1);
+}
+
 fn challenge_1(program: Vec<i32>) {
-    let output = Executor::run(program, vec![1]);
-    for err_code in output[..output.len() - 1].iter() {
-        assert_eq!(*err_code, 0);
+    let report = diagnostic::diagnose(program, 1);
+    if !report.passed() {
+        panic!("diagnostic tests failed:\n{}", report);
     }
-    println!("Challenge 1: Diagnostic code = {}", output.last().unwrap());
+    println!("Challenge 1: Diagnostic code = {}", report.code.unwrap());
 }
 
 fn challenge_2(program: Vec<i32>) {
@@ -0,0 +1,162 @@
+use crate::orbits::OrbitMap;
+use std::collections::HashSet;
+
+// Visits every object depth first from the root, children in map order, passing whether
+// each object is the last child of its parent.
+fn preorder(map: &OrbitMap) -> Vec<(usize, bool)> {
+    let mut order = vec![];
+    let mut stack = vec![(map.root(), true)];
+    while let Some((id, last)) = stack.pop() {
+        order.push((id, last));
+        let children = map.children(id);
+        for (i, &child) in children.iter().enumerate().rev() {
+            stack.push((child, i == children.len() - 1));
+        }
+    }
+    order
+}
+
+// The number of objects in the subtree of each object, including itself.
+fn subtree_sizes(map: &OrbitMap, order: &[(usize, bool)]) -> Vec<usize> {
+    let mut sizes = vec![1; order.len()];
+    for &(id, _) in order.iter().rev() {
+        if let Some(parent) = map.parent(id) {
+            sizes[parent] += sizes[id];
+        }
+    }
+    sizes
+}
+
+fn quote(label: &str) -> String {
+    format!("\"{}\"", label.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Renders the map as a Graphviz digraph with edges from each object to the objects
+/// orbiting it. Objects and orbits along `path` are drawn in red.
+pub fn dot(map: &OrbitMap, path: &[usize]) -> String {
+    let on_path: HashSet<usize> = path.iter().copied().collect();
+    let path_edges: HashSet<(usize, usize)> = path
+        .windows(2)
+        .map(|w| (w[0].min(w[1]), w[0].max(w[1])))
+        .collect();
+    let mut dot = String::from("digraph orbits {\n    node [shape=circle];\n");
+    for (id, _) in preorder(map) {
+        if let Some(parent) = map.parent(id) {
+            let style = if path_edges.contains(&(id.min(parent), id.max(parent))) {
+                " [color=red, penwidth=2]"
+            } else {
+                ""
+            };
+            dot.push_str(&format!(
+                "    {} -> {}{};\n",
+                quote(map.label(parent)),
+                quote(map.label(id)),
+                style
+            ));
+        }
+        if on_path.contains(&id) {
+            dot.push_str(&format!(
+                "    {} [color=red, fontcolor=red];\n",
+                quote(map.label(id))
+            ));
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Renders the map as an indented tree giving the depth of each object and the size of its
+/// subtree. Objects along `path` are marked with `*`.
+pub fn ascii(map: &OrbitMap, path: &[usize]) -> String {
+    let on_path: HashSet<usize> = path.iter().copied().collect();
+    let order = preorder(map);
+    let sizes = subtree_sizes(map, &order);
+    let mut tree = String::new();
+    // Whether each ancestor below the root still has children to come, so needs a rule.
+    let mut open: Vec<bool> = vec![];
+    for (id, last) in order {
+        let depth = map.depth(id);
+        open.truncate(depth.saturating_sub(1));
+        for &more in &open {
+            tree.push_str(if more { "│   " } else { "    " });
+        }
+        if depth > 0 {
+            tree.push_str(if last { "└── " } else { "├── " });
+            open.push(!last);
+        }
+        tree.push_str(&format!(
+            "{} (depth {}, size {}){}\n",
+            map.label(id),
+            depth,
+            sizes[id],
+            if on_path.contains(&id) { " *" } else { "" }
+        ));
+    }
+    tree
+}
+
+#[cfg(test)]
+mod export_tests {
+    use super::*;
+
+    fn example() -> OrbitMap {
+        OrbitMap::parse("COM)B\nB)C\nC)D\nB)G\nG)H\nD)YOU\nH)SAN").unwrap()
+    }
+
+    fn you_to_san(map: &OrbitMap) -> Vec<usize> {
+        map.path(map.id("YOU").unwrap(), map.id("SAN").unwrap())
+    }
+
+    #[test]
+    fn exports_dot() {
+        let map = example();
+        assert_eq!(
+            dot(&map, &you_to_san(&map)),
+            r#"digraph orbits {
+    node [shape=circle];
+    "COM" -> "B";
+    "B" [color=red, fontcolor=red];
+    "B" -> "C" [color=red, penwidth=2];
+    "C" [color=red, fontcolor=red];
+    "C" -> "D" [color=red, penwidth=2];
+    "D" [color=red, fontcolor=red];
+    "D" -> "YOU" [color=red, penwidth=2];
+    "YOU" [color=red, fontcolor=red];
+    "B" -> "G" [color=red, penwidth=2];
+    "G" [color=red, fontcolor=red];
+    "G" -> "H" [color=red, penwidth=2];
+    "H" [color=red, fontcolor=red];
+    "H" -> "SAN" [color=red, penwidth=2];
+    "SAN" [color=red, fontcolor=red];
+}
+"#
+        );
+        assert!(!dot(&map, &[]).contains("red"));
+    }
+
+    #[test]
+    fn exports_ascii_tree() {
+        let map = example();
+        assert_eq!(
+            ascii(&map, &you_to_san(&map)),
+            "COM (depth 0, size 8)
+└── B (depth 1, size 7) *
+    ├── C (depth 2, size 3) *
+    │   └── D (depth 3, size 2) *
+    │       └── YOU (depth 4, size 1) *
+    └── G (depth 2, size 3) *
+        └── H (depth 3, size 2) *
+            └── SAN (depth 4, size 1) *
+"
+        );
+        let map = OrbitMap::parse("A)B\nA)C\nB)D").unwrap();
+        assert_eq!(
+            ascii(&map, &[]),
+            "A (depth 0, size 4)
+├── B (depth 1, size 2)
+│   └── D (depth 2, size 1)
+└── C (depth 1, size 1)
+"
+        );
+    }
+}
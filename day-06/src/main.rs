@@ -1,27 +1,104 @@
-use std::cell::RefCell;
-use std::collections::HashMap;
-use std::collections::VecDeque;
+mod export;
+mod orbits;
+
+use orbits::OrbitMap;
+use std::env;
 use std::fs::File;
-use std::io::Read;
-use std::rc::Rc;
+use std::io::{self, BufRead, Read};
 
 fn main() {
-    let input = read_input("input");
-    challenge_1(&input);
-    challenge_2(&input);
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(|s| s.as_str()) {
+        None => {
+            let map = load_map("input");
+            challenge_1(&map);
+            challenge_2(&map);
+        }
+        Some("transfers") if args.len() == 3 => transfers(&load_map(&args[2])),
+        Some(format @ "dot") | Some(format @ "tree") if args.len() == 3 || args.len() == 5 => {
+            let map = load_map(&args[2]);
+            let path = match &args[3..] {
+                [from, to] => {
+                    let id = |label: &str| {
+                        map.id(label).unwrap_or_else(|| {
+                            eprintln!("unknown object {}", label);
+                            std::process::exit(1);
+                        })
+                    };
+                    map.path(id(from), id(to))
+                }
+                _ => vec![],
+            };
+            if format == "dot" {
+                print!("{}", export::dot(&map, &path));
+            } else {
+                print!("{}", export::ascii(&map, &path));
+            }
+        }
+        _ => usage(),
+    }
+}
+
+fn usage() -> ! {
+    let program = env::args().next().unwrap();
+    println!("Usage:");
+    println!("    {}", program);
+    println!("    {} transfers MAP < QUERIES", program);
+    println!("    {} dot MAP [FROM TO]", program);
+    println!("    {} tree MAP [FROM TO]", program);
+    println!();
+    println!("Arguments:");
+    println!("    map: file of PARENT)CHILD orbits");
+    println!("    queries: lines of two objects separated by whitespace");
+    println!("    from, to: objects whose connecting path is highlighted, e.g. YOU SAN");
+    std::process::exit(1);
+}
+
+fn load_map(filename: &str) -> OrbitMap {
+    OrbitMap::parse(&read_input(filename)).unwrap_or_else(|e| {
+        eprintln!("{}: {}", filename, e);
+        std::process::exit(1);
+    })
+}
+
+// Prints the transfers needed between each pair of objects read from stdin and the object
+// where their paths meet.
+fn transfers(map: &OrbitMap) {
+    for line in io::stdin().lock().lines() {
+        let line = line.unwrap();
+        let objects: Vec<&str> = line.split_whitespace().collect();
+        if objects.is_empty() {
+            continue;
+        }
+        let ids: Vec<Option<usize>> = objects.iter().map(|o| map.id(o)).collect();
+        match (objects.as_slice(), ids.as_slice()) {
+            ([a, b], [Some(a_id), Some(b_id)]) => match map.transfers(a, b) {
+                Some(n) => println!(
+                    "{} {} {} via {}",
+                    a,
+                    b,
+                    n,
+                    map.label(map.common_ancestor(*a_id, *b_id))
+                ),
+                None => println!("{} {} - (one of them orbits nothing)", a, b),
+            },
+            ([a, b], _) => println!("{} {} - (unknown object)", a, b),
+            _ => eprintln!("expected two objects: {:?}", line),
+        }
+    }
 }
 
-fn challenge_1(input: &str) {
+fn challenge_1(map: &OrbitMap) {
     println!(
         "Challenge 1: Total number of orbits = {}",
-        number_of_orbits(&input)
+        map.total_orbits()
     );
 }
 
-fn challenge_2(input: &str) {
+fn challenge_2(map: &OrbitMap) {
     println!(
         "Challenge 2: Number of orbital transfers = {}",
-        number_of_transfers("YOU", "SAN", Node::parse(&input))
+        map.transfers("YOU", "SAN").unwrap()
     );
 }
 
@@ -34,133 +111,29 @@ fn read_input(filename: &str) -> String {
     buffer
 }
 
-fn number_of_orbits(s: &str) -> u32 {
-    let root = Node::parse(s);
-    let mut orbits = 0;
-    let visit = Box::new(|node: RcNode| {
-        orbits += number_of_ancestors(Rc::clone(&node));
-    });
-    walk(root, visit);
-    orbits
-}
-
-fn number_of_transfers(o1: &str, o2: &str, tree: RcNode) -> i32 {
-    let path_to_o1 = path_to(o1, Rc::clone(&tree)).unwrap();
-    let path_to_o2 = path_to(o2, Rc::clone(&tree)).unwrap();
-    let mut common_path_length = 0;
-    for (p1, p2) in path_to_o1.iter().zip(&path_to_o2) {
-        if p1 != p2 {
-            break;
-        }
-        common_path_length += 1;
-    }
-    path_to_o1.len() as i32 + path_to_o2.len() as i32 - 2 * common_path_length
-}
-
-type RcNode = Rc<RefCell<Node>>;
-
-#[derive(Default, Debug)]
-struct Node {
-    label: String,
-    parent: Option<RcNode>,
-    children: Vec<RcNode>,
-}
-
-impl Node {
-    fn parse(input: &str) -> RcNode {
-        let mut nodes: HashMap<&str, RcNode> = Default::default();
-        for line in input.trim().lines() {
-            let parts: Vec<_> = line.trim().split(')').collect();
-            let parent_label = parts[0];
-            let child_label = parts[1];
-            match (nodes.get(parent_label), nodes.get(child_label)) {
-                (Some(parent), Some(child)) => {
-                    parent.borrow_mut().children.push(Rc::clone(&child));
-                    child.borrow_mut().parent = Some(Rc::clone(&parent));
-                }
-                (Some(parent), None) => {
-                    let child = Node::make(child_label, Some(Rc::clone(&parent)), Vec::new());
-                    parent.borrow_mut().children.push(Rc::clone(&child));
-                    nodes.insert(child_label, Rc::clone(&child));
-                }
-                (None, Some(child)) => {
-                    let parent = Node::make(parent_label, None, vec![Rc::clone(&child)]);
-                    child.borrow_mut().parent = Some(Rc::clone(&parent));
-                    nodes.insert(parent_label, Rc::clone(&parent));
-                }
-                (None, None) => {
-                    let parent = Node::make(parent_label, None, Vec::new());
-                    let child = Node::make(child_label, Some(Rc::clone(&parent)), Vec::new());
-                    parent.borrow_mut().children.push(Rc::clone(&child));
-                    nodes.insert(parent_label, Rc::clone(&parent));
-                    nodes.insert(child_label, Rc::clone(&child));
-                }
-            }
-        }
-        for (_, node) in nodes {
-            if node.borrow().parent.is_none() {
-                return node;
-            }
-        }
-        unreachable!();
-    }
+#[cfg(test)]
+mod tests {
+    #![allow(non_snake_case)]
 
-    fn make(label: &str, parent: Option<RcNode>, children: Vec<RcNode>) -> RcNode {
-        Rc::new(RefCell::new(Node {
-            label: label.to_owned(),
-            parent,
-            children,
-        }))
-    }
+    use super::*;
 
-    #[allow(dead_code)]
-    fn parent_label(&self) -> Option<String> {
-        match &self.parent {
-            Some(v) => Some(v.borrow().label.clone()),
-            None => None,
-        }
+    fn number_of_orbits(s: &str) -> usize {
+        OrbitMap::parse(s).unwrap().total_orbits()
     }
-}
 
-fn walk<F>(node: RcNode, mut visit: Box<F>) -> Box<F>
-where
-    F: FnMut(RcNode),
-{
-    visit(Rc::clone(&node));
-    for child in &node.borrow().children {
-        visit = walk(Rc::clone(&child), visit);
+    fn number_of_transfers(o1: &str, o2: &str, map: &OrbitMap) -> usize {
+        map.transfers(o1, o2).unwrap()
     }
-    visit
-}
 
-fn path_to(label: &str, node: RcNode) -> Option<VecDeque<String>> {
-    let node_label = node.borrow().label.to_owned();
-    if node_label == label {
-        return Some(VecDeque::new());
+    // The labels of the objects orbiting `label`.
+    fn children<'a>(map: &'a OrbitMap, label: &str) -> Vec<&'a str> {
+        let id = map.id(label).unwrap();
+        map.children(id).iter().map(|&c| map.label(c)).collect()
     }
-    for child in &node.borrow().children {
-        if let Some(mut path) = path_to(label, Rc::clone(&child)) {
-            path.push_front(node_label);
-            return Some(path);
-        }
-    }
-    None
-}
 
-fn number_of_ancestors(node: RcNode) -> u32 {
-    if node.borrow().parent.is_none() {
-        return 0;
+    fn parent_label<'a>(map: &'a OrbitMap, label: &str) -> Option<&'a str> {
+        map.parent(map.id(label).unwrap()).map(|p| map.label(p))
     }
-    let node = Rc::clone(&node);
-    let node = node.borrow();
-    number_of_ancestors(Rc::clone(node.parent.as_ref().unwrap())) + 1
-}
-
-#[cfg(test)]
-mod tests {
-    #![allow(non_snake_case)]
-
-    use super::*;
 
     #[test]
     fn reads_input() {
@@ -170,94 +143,80 @@ mod tests {
 
     #[test]
     fn builds_tree_for_single_case() {
-        let root = Node::parse("A)B");
-        let root = root.borrow();
-        assert_eq!(root.label, "A".to_owned());
-        assert_eq!(root.parent.is_some(), false);
-        assert_eq!(root.children.len(), 1);
-        let node_b = root.children[0].borrow();
-        assert_eq!(node_b.label, "B".to_owned());
-        assert_eq!(node_b.parent_label(), Some("A".to_owned()));
-        assert_eq!(node_b.children.len(), 0);
+        let map = OrbitMap::parse("A)B").unwrap();
+        assert_eq!(map.label(map.root()), "A");
+        assert_eq!(map.parent(map.root()), None);
+        assert_eq!(children(&map, "A").len(), 1);
+        assert_eq!(children(&map, "A")[0], "B");
+        assert_eq!(parent_label(&map, "B"), Some("A"));
+        assert_eq!(children(&map, "B").len(), 0);
     }
 
     #[test]
     fn builds_tree_for_B_C_orbits_A() {
-        let root = Node::parse("A)B\nA)C");
-        let root = root.borrow();
-        assert_eq!(root.label, "A".to_owned());
-        assert_eq!(root.parent.is_some(), false);
-        assert_eq!(root.children.len(), 2);
-        let node_b = root.children[0].borrow();
-        assert_eq!(node_b.label, "B".to_owned());
-        assert_eq!(node_b.parent_label(), Some("A".to_owned()));
-        assert_eq!(node_b.children.len(), 0);
-        let node_c = root.children[1].borrow();
-        assert_eq!(node_c.label, "C".to_owned());
-        assert_eq!(node_c.parent_label(), Some("A".to_owned()));
-        assert_eq!(node_c.children.len(), 0);
+        let map = OrbitMap::parse("A)B\nA)C").unwrap();
+        assert_eq!(map.label(map.root()), "A");
+        assert_eq!(map.parent(map.root()), None);
+        assert_eq!(children(&map, "A").len(), 2);
+        assert_eq!(children(&map, "A")[0], "B");
+        assert_eq!(parent_label(&map, "B"), Some("A"));
+        assert_eq!(children(&map, "B").len(), 0);
+        assert_eq!(children(&map, "A")[1], "C");
+        assert_eq!(parent_label(&map, "C"), Some("A"));
+        assert_eq!(children(&map, "C").len(), 0);
     }
 
     #[test]
     fn builds_tree_for_C_orbits_B_orbits_A() {
-        let root = Node::parse("A)B\nB)C");
-        let root = root.borrow();
-        assert_eq!(root.label, "A".to_owned());
-        assert_eq!(root.parent.is_some(), false);
-        assert_eq!(root.children.len(), 1);
-        let node_b = root.children[0].borrow();
-        assert_eq!(node_b.label, "B".to_owned());
-        assert_eq!(node_b.parent_label(), Some("A".to_owned()));
-        assert_eq!(node_b.children.len(), 1);
-        let node_c = node_b.children[0].borrow();
-        assert_eq!(node_c.label, "C".to_owned());
-        assert_eq!(node_c.parent_label(), Some("B".to_owned()));
-        assert_eq!(node_c.children.len(), 0);
+        let map = OrbitMap::parse("A)B\nB)C").unwrap();
+        assert_eq!(map.label(map.root()), "A");
+        assert_eq!(map.parent(map.root()), None);
+        assert_eq!(children(&map, "A").len(), 1);
+        assert_eq!(children(&map, "A")[0], "B");
+        assert_eq!(parent_label(&map, "B"), Some("A"));
+        assert_eq!(children(&map, "B").len(), 1);
+        assert_eq!(children(&map, "B")[0], "C");
+        assert_eq!(parent_label(&map, "C"), Some("B"));
+        assert_eq!(children(&map, "C").len(), 0);
     }
 
     #[test]
     fn builds_tree_where_node_created_as_child_before_parent() {
-        let root = Node::parse("B)C\nA)B");
-        let root = root.borrow();
-        assert_eq!(root.label, "A".to_owned());
-        assert_eq!(root.parent.is_some(), false);
-        assert_eq!(root.children.len(), 1);
-        let node_b = root.children[0].borrow();
-        assert_eq!(node_b.label, "B".to_owned());
-        assert_eq!(node_b.parent_label(), Some("A".to_owned()));
-        assert_eq!(node_b.children.len(), 1);
-        let node_c = node_b.children[0].borrow();
-        assert_eq!(node_c.label, "C".to_owned());
-        assert_eq!(node_c.parent_label(), Some("B".to_owned()));
-        assert_eq!(node_c.children.len(), 0);
+        let map = OrbitMap::parse("B)C\nA)B").unwrap();
+        assert_eq!(map.label(map.root()), "A");
+        assert_eq!(map.parent(map.root()), None);
+        assert_eq!(children(&map, "A").len(), 1);
+        assert_eq!(children(&map, "A")[0], "B");
+        assert_eq!(parent_label(&map, "B"), Some("A"));
+        assert_eq!(children(&map, "B").len(), 1);
+        assert_eq!(children(&map, "B")[0], "C");
+        assert_eq!(parent_label(&map, "C"), Some("B"));
+        assert_eq!(children(&map, "C").len(), 0);
     }
 
     #[test]
     fn builds_tree_where_child_and_parent_created_seperately_before_linked() {
         // A - B - C - D
-        let root = Node::parse(
+        let map = OrbitMap::parse(
             r#"
             A)B
             C)D
             B)C
             "#,
-        );
-        let root = root.borrow();
-        assert_eq!(root.label, "A".to_owned());
-        assert_eq!(root.parent.is_some(), false);
-        assert_eq!(root.children.len(), 1);
-        let node_b = root.children[0].borrow();
-        assert_eq!(node_b.label, "B".to_owned());
-        assert_eq!(node_b.parent_label(), Some("A".to_owned()));
-        assert_eq!(node_b.children.len(), 1);
-        let node_c = node_b.children[0].borrow();
-        assert_eq!(node_c.label, "C".to_owned());
-        assert_eq!(node_c.parent_label(), Some("B".to_owned()));
-        assert_eq!(node_c.children.len(), 1);
-        let node_d = node_c.children[0].borrow();
-        assert_eq!(node_d.label, "D".to_owned());
-        assert_eq!(node_d.parent_label(), Some("C".to_owned()));
-        assert_eq!(node_d.children.len(), 0);
+        )
+        .unwrap();
+        assert_eq!(map.label(map.root()), "A");
+        assert_eq!(map.parent(map.root()), None);
+        assert_eq!(children(&map, "A").len(), 1);
+        assert_eq!(children(&map, "A")[0], "B");
+        assert_eq!(parent_label(&map, "B"), Some("A"));
+        assert_eq!(children(&map, "B").len(), 1);
+        assert_eq!(children(&map, "B")[0], "C");
+        assert_eq!(parent_label(&map, "C"), Some("B"));
+        assert_eq!(children(&map, "C").len(), 1);
+        assert_eq!(children(&map, "C")[0], "D");
+        assert_eq!(parent_label(&map, "D"), Some("C"));
+        assert_eq!(children(&map, "D").len(), 0);
     }
 
     #[test]
@@ -310,35 +269,38 @@ mod tests {
         //  A ― X
         //   \
         //    Y
-        let tree = Node::parse(
+        let tree = OrbitMap::parse(
             r#"
             A)X
             A)Y
             "#,
-        );
-        assert_eq!(number_of_transfers("X", "Y", Rc::clone(&tree)), 0);
+        )
+        .unwrap();
+        assert_eq!(number_of_transfers("X", "Y", &tree), 0);
         //  A ― B ― X
         //   \
         //    Y
-        let tree = Node::parse(
+        let tree = OrbitMap::parse(
             r#"
             A)B
             B)X
             A)Y
             "#,
-        );
-        assert_eq!(number_of_transfers("X", "Y", Rc::clone(&tree)), 1);
+        )
+        .unwrap();
+        assert_eq!(number_of_transfers("X", "Y", &tree), 1);
         //  A ― B ― X
         //   \
         //    C ― Y
-        let tree = Node::parse(
+        let tree = OrbitMap::parse(
             r#"
             A)B
             A)C
             B)X
             C)Y
             "#,
-        );
-        assert_eq!(number_of_transfers("X", "Y", Rc::clone(&tree)), 2);
+        )
+        .unwrap();
+        assert_eq!(number_of_transfers("X", "Y", &tree), 2);
     }
 }
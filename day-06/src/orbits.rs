@@ -0,0 +1,354 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    InvalidLine {
+        line: usize,
+        text: String,
+    },
+    DuplicateParent {
+        line: usize,
+        object: String,
+        parents: (String, String),
+    },
+    /// The objects in a cycle, each orbiting the next and the last orbiting the first.
+    Cycle(Vec<String>),
+    MultipleRoots(Vec<String>),
+    Empty,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::InvalidLine { line, text } => {
+                write!(f, "line {}: expected PARENT)CHILD, got {:?}", line, text)
+            }
+            ParseError::DuplicateParent {
+                line,
+                object,
+                parents,
+            } => write!(
+                f,
+                "line {}: {} orbits {} but already orbits {}",
+                line, object, parents.1, parents.0
+            ),
+            ParseError::Cycle(objects) => write!(
+                f,
+                "orbits form a cycle: {} orbits {}",
+                objects.join(" orbits "),
+                objects[0]
+            ),
+            ParseError::MultipleRoots(roots) => write!(
+                f,
+                "expected one object that orbits nothing, found {}",
+                roots.join(", ")
+            ),
+            ParseError::Empty => write!(f, "no orbits"),
+        }
+    }
+}
+
+/// The objects of an orbit map stored by index, with every object reachable from a single
+/// root.
+#[derive(Debug)]
+pub struct OrbitMap {
+    labels: Vec<String>,
+    ids: HashMap<String, usize>,
+    parents: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+    depths: Vec<usize>,
+    root: usize,
+    // ancestors[k][id] is the object 2^k steps towards the root from `id`, or the root.
+    ancestors: Vec<Vec<usize>>,
+}
+
+impl OrbitMap {
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        let mut labels: Vec<String> = vec![];
+        let mut ids: HashMap<String, usize> = HashMap::new();
+        let mut parents: Vec<Option<usize>> = vec![];
+        let mut intern = |label: &str, parents: &mut Vec<Option<usize>>| {
+            *ids.entry(label.to_string()).or_insert_with(|| {
+                labels.push(label.to_string());
+                parents.push(None);
+                labels.len() - 1
+            })
+        };
+        for (i, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let parts: Vec<&str> = line.split(')').collect();
+            let (parent, child) = match parts.as_slice() {
+                [parent, child] if !parent.is_empty() && !child.is_empty() => (parent, child),
+                _ => {
+                    return Err(ParseError::InvalidLine {
+                        line: i + 1,
+                        text: line.to_string(),
+                    })
+                }
+            };
+            let parent = intern(parent, &mut parents);
+            let child = intern(child, &mut parents);
+            match parents[child] {
+                Some(existing) if existing != parent => {
+                    return Err(ParseError::DuplicateParent {
+                        line: i + 1,
+                        object: labels[child].clone(),
+                        parents: (labels[existing].clone(), labels[parent].clone()),
+                    })
+                }
+                _ => parents[child] = Some(parent),
+            }
+        }
+        if labels.is_empty() {
+            return Err(ParseError::Empty);
+        }
+
+        let mut children = vec![vec![]; labels.len()];
+        for (child, parent) in parents.iter().enumerate() {
+            if let Some(parent) = *parent {
+                children[parent].push(child);
+            }
+        }
+        let roots: Vec<usize> = (0..labels.len())
+            .filter(|&id| parents[id].is_none())
+            .collect();
+        let mut depths = vec![usize::MAX; labels.len()];
+        let mut queue: VecDeque<usize> = roots.iter().copied().collect();
+        for &root in &roots {
+            depths[root] = 0;
+        }
+        while let Some(id) = queue.pop_front() {
+            for &child in &children[id] {
+                depths[child] = depths[id] + 1;
+                queue.push_back(child);
+            }
+        }
+        // Every object has at most one parent, so any object not reached from a root must
+        // lead up to a cycle.
+        if let Some(start) = (0..labels.len()).find(|&id| depths[id] == usize::MAX) {
+            let mut seen = vec![false; labels.len()];
+            let mut id = start;
+            while !seen[id] {
+                seen[id] = true;
+                id = parents[id].unwrap();
+            }
+            let mut cycle = vec![labels[id].clone()];
+            let mut next = parents[id].unwrap();
+            while next != id {
+                cycle.push(labels[next].clone());
+                next = parents[next].unwrap();
+            }
+            return Err(ParseError::Cycle(cycle));
+        }
+        if roots.len() > 1 {
+            let mut roots: Vec<String> = roots.iter().map(|&id| labels[id].clone()).collect();
+            roots.sort();
+            return Err(ParseError::MultipleRoots(roots));
+        }
+
+        let root = roots[0];
+        let max_depth = depths.iter().copied().max().unwrap();
+        let mut ancestors = vec![parents
+            .iter()
+            .map(|p| p.unwrap_or(root))
+            .collect::<Vec<_>>()];
+        while 1 << ancestors.len() <= max_depth {
+            let last = ancestors.last().unwrap();
+            let next = last.iter().map(|&id| last[id]).collect();
+            ancestors.push(next);
+        }
+        Ok(Self {
+            labels,
+            ids,
+            parents,
+            children,
+            depths,
+            root,
+            ancestors,
+        })
+    }
+
+    pub fn id(&self, label: &str) -> Option<usize> {
+        self.ids.get(label).copied()
+    }
+
+    pub fn label(&self, id: usize) -> &str {
+        &self.labels[id]
+    }
+
+    pub fn root(&self) -> usize {
+        self.root
+    }
+
+    pub fn parent(&self, id: usize) -> Option<usize> {
+        self.parents[id]
+    }
+
+    /// The objects orbiting `id`, in the order they first appear in the map.
+    pub fn children(&self, id: usize) -> &[usize] {
+        &self.children[id]
+    }
+
+    /// The number of objects `id` orbits directly or indirectly.
+    pub fn depth(&self, id: usize) -> usize {
+        self.depths[id]
+    }
+
+    pub fn total_orbits(&self) -> usize {
+        self.depths.iter().sum()
+    }
+
+    fn ancestor(&self, mut id: usize, steps: usize) -> usize {
+        for (k, ancestors) in self.ancestors.iter().enumerate() {
+            if steps >> k & 1 == 1 {
+                id = ancestors[id];
+            }
+        }
+        id
+    }
+
+    /// The deepest object that both `a` and `b` are, or orbit.
+    pub fn common_ancestor(&self, a: usize, b: usize) -> usize {
+        let (a, b) = if self.depths[a] > self.depths[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        let mut a = self.ancestor(a, self.depths[a] - self.depths[b]);
+        let mut b = b;
+        if a == b {
+            return a;
+        }
+        for ancestors in self.ancestors.iter().rev() {
+            if ancestors[a] != ancestors[b] {
+                a = ancestors[a];
+                b = ancestors[b];
+            }
+        }
+        self.ancestors[0][a]
+    }
+
+    /// The objects from `a` to `b` through their common ancestor, including both ends.
+    pub fn path(&self, a: usize, b: usize) -> Vec<usize> {
+        let common = self.common_ancestor(a, b);
+        let climb = |mut id: usize| {
+            let mut path = vec![];
+            while id != common {
+                path.push(id);
+                id = self.parents[id].unwrap();
+            }
+            path
+        };
+        let mut path = climb(a);
+        path.push(common);
+        path.extend(climb(b).into_iter().rev());
+        path
+    }
+
+    /// The number of orbital transfers needed to move from the object `a` orbits to the
+    /// object `b` orbits.
+    pub fn transfers(&self, a: &str, b: &str) -> Option<usize> {
+        let (a, b) = (self.id(a)?, self.id(b)?);
+        let (a, b) = (self.parent(a)?, self.parent(b)?);
+        Some(self.depths[a] + self.depths[b] - 2 * self.depths[self.common_ancestor(a, b)])
+    }
+}
+
+#[cfg(test)]
+mod orbits_tests {
+    use super::*;
+
+    fn example() -> OrbitMap {
+        OrbitMap::parse("COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L\nK)YOU\nI)SAN")
+            .unwrap()
+    }
+
+    #[test]
+    fn finds_common_ancestors() {
+        let map = example();
+        let ancestor =
+            |a, b| map.label(map.common_ancestor(map.id(a).unwrap(), map.id(b).unwrap()));
+        assert_eq!(ancestor("YOU", "SAN"), "D");
+        assert_eq!(ancestor("L", "F"), "E");
+        assert_eq!(ancestor("H", "L"), "B");
+        assert_eq!(ancestor("K", "YOU"), "K");
+        assert_eq!(ancestor("COM", "COM"), "COM");
+    }
+
+    #[test]
+    fn finds_paths() {
+        let map = example();
+        let path = |a, b| -> Vec<&str> {
+            map.path(map.id(a).unwrap(), map.id(b).unwrap())
+                .into_iter()
+                .map(|id| map.label(id))
+                .collect()
+        };
+        assert_eq!(
+            path("YOU", "SAN"),
+            vec!["YOU", "K", "J", "E", "D", "I", "SAN"]
+        );
+        assert_eq!(path("C", "F"), vec!["C", "D", "E", "F"]);
+        assert_eq!(path("H", "H"), vec!["H"]);
+    }
+
+    #[test]
+    fn counts_transfers() {
+        let map = example();
+        assert_eq!(map.transfers("YOU", "SAN"), Some(4));
+        assert_eq!(map.transfers("L", "YOU"), Some(0));
+        assert_eq!(map.transfers("COM", "SAN"), None);
+        assert_eq!(map.transfers("YOU", "BOB"), None);
+    }
+
+    #[test]
+    fn matches_naive_ancestors_on_long_chains() {
+        let input: Vec<String> = (0..300)
+            .map(|i| format!("{}){}", if i % 7 == 0 { 0 } else { i - 1 }, i + 1))
+            .collect();
+        let map = OrbitMap::parse(&input.join("\n")).unwrap();
+        let path = |mut id: usize| {
+            let mut path = vec![id];
+            while let Some(parent) = map.parent(id) {
+                path.push(parent);
+                id = parent;
+            }
+            path
+        };
+        for a in (0..map.labels.len()).step_by(13) {
+            for b in (0..map.labels.len()).step_by(17) {
+                let (path_a, path_b) = (path(a), path(b));
+                let naive = *path_a.iter().find(|id| path_b.contains(id)).unwrap();
+                assert_eq!(map.common_ancestor(a, b), naive);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_maps() {
+        let error = |input| OrbitMap::parse(input).unwrap_err().to_string();
+        assert_eq!(
+            error("A)B\nB-C"),
+            "line 2: expected PARENT)CHILD, got \"B-C\""
+        );
+        assert_eq!(error("A)B\nC)B"), "line 2: B orbits C but already orbits A");
+        assert_eq!(
+            error("A)B\nB)C\nC)A"),
+            "orbits form a cycle: A orbits C orbits B orbits A"
+        );
+        assert_eq!(
+            error("R)A\nA)B\nC)D\nD)C"),
+            "orbits form a cycle: C orbits D orbits C"
+        );
+        assert_eq!(
+            error("A)B\nC)D\nE)F"),
+            "expected one object that orbits nothing, found A, C, E"
+        );
+        assert_eq!(error("\n"), "no orbits");
+        assert!(OrbitMap::parse("A)B\nA)B").is_ok());
+    }
+}
@@ -1,14 +1,87 @@
+mod search;
+
 use intcode_computer::Computer;
-use std::collections::HashSet;
+use std::env;
 use std::fs::File;
 use std::io::Read;
 
-const NUM_AMPLIFIERS: u32 = 5;
-const NUM_PHASE_OPTIONS: i32 = 5;
-
 fn main() {
-    challenge_1();
-    challenge_2();
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(|s| s.as_str()) {
+        None => {
+            challenge_1();
+            challenge_2();
+        }
+        Some("rank") if (4..=7).contains(&args.len()) => rank(&args[2..]),
+        _ => usage(),
+    }
+}
+
+fn usage() -> ! {
+    let program = env::args().next().unwrap();
+    println!("Usage:");
+    println!("    {}", program);
+    println!(
+        "    {} rank MODE PHASES [AMPLIFIERS [TOP [TARGET]]]",
+        program
+    );
+    println!();
+    println!("Arguments:");
+    println!("    mode: serial or feedback");
+    println!("    phases: comma separated phase settings to choose from, e.g. 5,6,7,8,9");
+    println!("    amplifiers: number of amplifiers (default one per phase)");
+    println!("    top: number of settings to list (default 10)");
+    println!("    target: stop once a setting gives at least this signal");
+    std::process::exit(1);
+}
+
+fn rank(args: &[String]) {
+    let signal_func = match args[0].as_str() {
+        "serial" => calc_signal,
+        "feedback" => calc_signal_with_feedback,
+        _ => usage(),
+    };
+    let phases: Vec<i32> = args[1]
+        .split(',')
+        .map(|p| p.trim().parse().unwrap_or_else(|_| usage()))
+        .collect();
+    let arg = |i: usize, default: usize| {
+        args.get(i)
+            .map_or(default, |a| a.parse().unwrap_or_else(|_| usage()))
+    };
+    let amplifiers = arg(2, phases.len());
+    let top = arg(3, 10);
+    let target = args.get(4).map(|a| a.parse().unwrap_or_else(|_| usage()));
+    if amplifiers == 0 {
+        eprintln!("need at least one amplifier");
+        usage();
+    }
+    if let Some(phase) = phases
+        .iter()
+        .enumerate()
+        .find_map(|(i, p)| phases[..i].contains(p).then_some(p))
+    {
+        eprintln!("phase {} is given more than once", phase);
+        usage();
+    }
+    if amplifiers > phases.len() {
+        eprintln!("need at least {} phases", amplifiers);
+        usage();
+    }
+    let program = load_input("input");
+    let ranking = search::rank(&phases, amplifiers, top, target, |phases| {
+        signal_func(program.clone(), phases.to_vec())
+    });
+    for (i, setting) in ranking.top.iter().enumerate() {
+        println!("{:>3}. {:?} {}", i + 1, setting.phases, setting.signal);
+    }
+    match ranking.reached {
+        Some(setting) => println!(
+            "Reached {} with {:?} after {} settings",
+            setting.signal, setting.phases, ranking.evaluated
+        ),
+        None => println!("Evaluated {} settings", ranking.evaluated),
+    }
 }
 
 fn challenge_1() {
@@ -37,48 +110,21 @@ fn load_input(filename: &str) -> Vec<i32> {
 }
 
 fn max_signal(program: Vec<i32>) -> i32 {
-    find_max_signal(program, 0, calc_signal)
+    find_max_signal(program, &[0, 1, 2, 3, 4], calc_signal)
 }
 
 fn max_signal_with_feedback(program: Vec<i32>) -> i32 {
-    find_max_signal(program, 5, calc_signal_with_feedback)
+    find_max_signal(program, &[5, 6, 7, 8, 9], calc_signal_with_feedback)
 }
 
-fn find_max_signal<F>(program: Vec<i32>, phase_offset: i32, signal_func: F) -> i32
+fn find_max_signal<F>(program: Vec<i32>, phases: &[i32], signal_func: F) -> i32
 where
-    F: Fn(Vec<i32>, Vec<i32>) -> i32,
+    F: Fn(Vec<i32>, Vec<i32>) -> i32 + Sync,
 {
-    let mut max_signal = i32::min_value();
-    for phase_counter in 0..=NUM_PHASE_OPTIONS.pow(NUM_AMPLIFIERS) {
-        let phases = phases_from_counter(phase_counter as i32, phase_offset);
-        if contains_duplicates(&phases) {
-            continue;
-        }
-        let signal = signal_func(program.clone(), phases);
-        if signal > max_signal {
-            max_signal = signal;
-        }
-    }
-    max_signal
-}
-
-fn phases_from_counter(mut counter: i32, offset: i32) -> Vec<i32> {
-    let mut phases = Vec::new();
-    for _ in 1..=NUM_AMPLIFIERS {
-        phases.push(counter % NUM_PHASE_OPTIONS + offset);
-        counter /= NUM_PHASE_OPTIONS;
-    }
-    phases
-}
-
-fn contains_duplicates(values: &[i32]) -> bool {
-    let mut seen = HashSet::new();
-    for v in values {
-        if !seen.insert(v) {
-            return true;
-        }
-    }
-    false
+    let ranking = search::rank(phases, phases.len(), 1, None, |phases| {
+        signal_func(program.clone(), phases.to_vec())
+    });
+    ranking.top[0].signal
 }
 
 fn calc_signal(program: Vec<i32>, phases: Vec<i32>) -> i32 {
@@ -92,7 +138,7 @@ fn calc_signal(program: Vec<i32>, phases: Vec<i32>) -> i32 {
 
 fn calc_signal_with_feedback(program: Vec<i32>, phases: Vec<i32>) -> i32 {
     let mut computers = Vec::new();
-    for _ in 1..=NUM_AMPLIFIERS {
+    for _ in 0..phases.len() {
         computers.push(Computer::load(program.clone()));
     }
     for (computer, phase) in computers.iter_mut().zip(&phases) {
@@ -103,7 +149,7 @@ fn calc_signal_with_feedback(program: Vec<i32>, phases: Vec<i32>) -> i32 {
         for (i, computer) in computers.iter_mut().enumerate() {
             let (out, complete) = computer.run(vec![prev_signal]);
             prev_signal = out[0];
-            if complete && i == (NUM_AMPLIFIERS - 1) as usize {
+            if complete && i == phases.len() - 1 {
                 return prev_signal;
             }
         }
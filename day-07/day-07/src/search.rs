@@ -0,0 +1,201 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setting {
+    pub phases: Vec<i32>,
+    pub signal: i32,
+}
+
+#[derive(Debug)]
+pub struct Ranking {
+    /// The best settings, highest signal first, with ties in the order the settings are
+    /// enumerated.
+    pub top: Vec<Setting>,
+    pub evaluated: usize,
+    /// A setting that reached the target signal, if the search stopped early. The ranking
+    /// then only covers the settings evaluated before stopping.
+    pub reached: Option<Setting>,
+}
+
+// The number of ways to arrange `k` of `n` items.
+fn arrangement_count(n: usize, k: usize) -> usize {
+    (n - k + 1..=n)
+        .try_fold(1usize, |acc, v| acc.checked_mul(v))
+        .expect("search space too large")
+}
+
+/// The `index`th way of giving `amplifiers` amplifiers distinct phases from `phases`, in
+/// lexicographic order of the positions of the chosen phases.
+pub fn arrangement(phases: &[i32], amplifiers: usize, mut index: usize) -> Vec<i32> {
+    let mut pool = phases.to_vec();
+    let mut chosen = vec![];
+    for i in 0..amplifiers {
+        let per_choice = arrangement_count(pool.len() - 1, amplifiers - i - 1);
+        chosen.push(pool.remove(index / per_choice));
+        index %= per_choice;
+    }
+    chosen
+}
+
+// Inserts a setting into a list of at most `top` settings ordered best first.
+fn insert(best: &mut Vec<(usize, Setting)>, top: usize, index: usize, setting: Setting) {
+    let pos = best
+        .iter()
+        .position(|(i, s)| {
+            (s.signal, std::cmp::Reverse(*i)) < (setting.signal, std::cmp::Reverse(index))
+        })
+        .unwrap_or(best.len());
+    if pos < top {
+        best.insert(pos, (index, setting));
+        best.truncate(top);
+    }
+}
+
+/// Evaluates `signal` for every way of giving `amplifiers` amplifiers distinct phases from
+/// `phases` across all available threads, keeping the best `top` settings. If `target` is
+/// given the search stops once any setting reaches it.
+pub fn rank<F>(
+    phases: &[i32],
+    amplifiers: usize,
+    top: usize,
+    target: Option<i32>,
+    signal: F,
+) -> Ranking
+where
+    F: Fn(&[i32]) -> i32 + Sync,
+{
+    assert!(
+        amplifiers <= phases.len(),
+        "{} amplifiers need at least as many phases, got {:?}",
+        amplifiers,
+        phases
+    );
+    assert!(
+        phases
+            .iter()
+            .enumerate()
+            .all(|(i, p)| !phases[..i].contains(p)),
+        "phases must be distinct, got {:?}",
+        phases
+    );
+    let count = arrangement_count(phases.len(), amplifiers);
+    let next = AtomicUsize::new(0);
+    let evaluated = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let best = Mutex::new(vec![]);
+    let reached = Mutex::new(None);
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                let mut local = vec![];
+                while !stop.load(Ordering::SeqCst) {
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    if index >= count {
+                        break;
+                    }
+                    let phases = arrangement(phases, amplifiers, index);
+                    let setting = Setting {
+                        signal: signal(&phases),
+                        phases,
+                    };
+                    evaluated.fetch_add(1, Ordering::SeqCst);
+                    if target.is_some_and(|target| setting.signal >= target) {
+                        stop.store(true, Ordering::SeqCst);
+                        reached.lock().unwrap().get_or_insert(setting.clone());
+                    }
+                    insert(&mut local, top, index, setting);
+                }
+                let mut best = best.lock().unwrap();
+                for (index, setting) in local {
+                    insert(&mut best, top, index, setting);
+                }
+            });
+        }
+    });
+    Ranking {
+        top: best
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|(_, setting)| setting)
+            .collect(),
+        evaluated: evaluated.into_inner(),
+        reached: reached.into_inner().unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod search_tests {
+    use super::*;
+
+    #[test]
+    fn enumerates_arrangements() {
+        let all: Vec<Vec<i32>> = (0..arrangement_count(3, 2))
+            .map(|i| arrangement(&[7, 8, 9], 2, i))
+            .collect();
+        assert_eq!(
+            all,
+            vec![
+                vec![7, 8],
+                vec![7, 9],
+                vec![8, 7],
+                vec![8, 9],
+                vec![9, 7],
+                vec![9, 8]
+            ]
+        );
+        assert_eq!(arrangement_count(5, 5), 120);
+        assert_eq!(arrangement(&[0, 1, 2, 3, 4], 5, 119), vec![4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn ranks_settings() {
+        // Rewards high phases early on.
+        let signal = |phases: &[i32]| phases.iter().fold(0, |acc, p| acc * 10 + p);
+        let ranking = rank(&[1, 2, 3, 4], 3, 4, None, signal);
+        assert_eq!(ranking.evaluated, 24);
+        assert_eq!(ranking.reached, None);
+        let top: Vec<(Vec<i32>, i32)> = ranking
+            .top
+            .into_iter()
+            .map(|s| (s.phases, s.signal))
+            .collect();
+        assert_eq!(
+            top,
+            vec![
+                (vec![4, 3, 2], 432),
+                (vec![4, 3, 1], 431),
+                (vec![4, 2, 3], 423),
+                (vec![4, 2, 1], 421)
+            ]
+        );
+
+        let ranking = rank(&[1, 2, 3], 2, 10, None, |_| 0);
+        let phases: Vec<Vec<i32>> = ranking.top.into_iter().map(|s| s.phases).collect();
+        assert_eq!(phases[0], vec![1, 2]);
+        assert_eq!(phases.len(), 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "phases must be distinct, got [5, 5, 6]")]
+    fn refuses_repeated_phases() {
+        rank(&[5, 5, 6], 3, 10, None, |_| 0);
+    }
+
+    #[test]
+    fn stops_once_target_is_reached() {
+        // Only a handful of settings around 4, 5, 6, 7, 8, 9 come within 10 of the peak.
+        let signal =
+            |phases: &[i32]| -(phases.iter().fold(0, |acc, p| acc * 10 + p) - 456_789).abs();
+        let phases: Vec<i32> = (0..10).collect();
+        let ranking = rank(&phases, 6, 1, Some(-10), signal);
+        let reached = ranking.reached.unwrap();
+        assert!(reached.signal >= -10);
+        assert_eq!(signal(&reached.phases), reached.signal);
+        assert!(ranking.evaluated > arrangement_count(10, 6) / 3);
+        assert!(ranking.evaluated < arrangement_count(10, 6));
+    }
+}
@@ -172,6 +172,7 @@ impl Computer {
 }
 
 #[cfg(test)]
+#[allow(clippy::inconsistent_digit_grouping)]
 mod computer_tests {
     use super::*;
 
@@ -420,11 +421,11 @@ mod computer_tests {
         let mut computer = Computer::load(program);
         let (out, complete) = computer.run(vec![101]);
         assert_eq!(out, vec![99]);
-        assert_eq!(complete, false);
+        assert!(!complete);
 
         let (out, complete) = computer.run(vec![102]);
         assert_eq!(out, vec![102]);
-        assert_eq!(complete, true);
+        assert!(complete);
     }
 
     #[test]
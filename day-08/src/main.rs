@@ -1,110 +1,102 @@
-use std::fs::File;
-use std::io::Read;
+mod sif;
 
-const BLACK: u32 = 0;
-const WHITE: u32 = 1;
-const TRANSPARENT: u32 = 2;
+use sif::{Image, Palette};
+use std::env;
+use std::fs;
 
 const WIDTH: usize = 25;
 const HEIGHT: usize = 6;
 
 fn main() {
-    challenge_1();
-    challenge_2();
-}
-
-fn challenge_1() {
-    let solution = solve_challenge_1(read_data(), WIDTH, HEIGHT);
-    println!("Challenge 1: Solution = {}", solution);
-}
-
-fn challenge_2() {
-    let layers = build_layers(read_data(), WIDTH, HEIGHT);
-    let layer = flatten(&layers);
-    println!("Challenge 2:");
-    print!("{}", to_string(&layer, WIDTH, HEIGHT));
-}
-
-fn solve_challenge_1(data: Vec<u32>, width: usize, height: usize) -> u32 {
-    let data = build_layers(data, width, height);
-    let idx = layer_with_least_of_digit(0, &data);
-    let layer = &data[idx];
-    count_digit(1, layer) * count_digit(2, layer)
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(|s| s.as_str()) {
+        None => {
+            challenge_1();
+            challenge_2();
+        }
+        Some("text") if args.len() == 5 => {
+            print!("{}", load_image(&args[2], &args[3], &args[4]).to_text());
+        }
+        Some("export") if (6..=8).contains(&args.len()) => {
+            let image = load_image(&args[2], &args[3], &args[4]);
+            let output = &args[5];
+            let palette = args.get(6).map_or_else(Palette::standard, |name| {
+                Palette::by_name(name).unwrap_or_else(|| usage())
+            });
+            let scale = match args.get(7).map(|s| s.parse()) {
+                None => 1,
+                Some(Ok(scale)) if scale >= 1 => scale,
+                Some(_) => usage(),
+            };
+            let data = if output.ends_with(".png") {
+                image.to_png(&palette, scale)
+            } else if output.ends_with(".ppm") {
+                image.to_ppm(&palette, scale, [0, 0, 0])
+            } else {
+                eprintln!("unknown image format {}", output);
+                usage();
+            };
+            let data = data.unwrap_or_else(|e| {
+                eprintln!("{}: {}", output, e);
+                std::process::exit(1);
+            });
+            fs::write(output, data).unwrap();
+        }
+        _ => usage(),
+    }
 }
 
-fn read_data() -> Vec<u32> {
-    let mut input = String::new();
-    File::open("input")
-        .unwrap()
-        .read_to_string(&mut input)
-        .unwrap();
-    input
-        .trim()
-        .chars()
-        .map(|c| c.to_string().parse::<u32>().unwrap())
-        .collect()
+fn usage() -> ! {
+    let program = env::args().next().unwrap();
+    println!("Usage:");
+    println!("    {}", program);
+    println!("    {} text WIDTH HEIGHT INPUT", program);
+    println!(
+        "    {} export WIDTH HEIGHT INPUT OUTPUT [PALETTE [SCALE]]",
+        program
+    );
+    println!();
+    println!("Arguments:");
+    println!("    width, height: size of the image in pixels");
+    println!("    input: file of Space Image Format digits");
+    println!("    output: image file to write, ending in .png or .ppm");
+    println!("    palette: standard or extended (default standard)");
+    println!("    scale: size of each pixel in the output, at least 1 (default 1)");
+    std::process::exit(1);
 }
 
-fn build_layers(data: Vec<u32>, width: usize, height: usize) -> Vec<Vec<u32>> {
-    let mut layers = Vec::new();
-    let num_layers = data.len() / (width * height);
-    for layer_index in 0..num_layers {
-        let start = layer_index * width * height;
-        let end = start + width * height;
-        layers.push(data[start..end].to_vec());
-    }
-    layers
+fn load_image(width: &str, height: &str, filename: &str) -> Image {
+    let width = width.parse().unwrap_or_else(|_| usage());
+    let height = height.parse().unwrap_or_else(|_| usage());
+    Image::decode(&read_data(filename), width, height).unwrap_or_else(|e| {
+        eprintln!("{}: {}", filename, e);
+        std::process::exit(1);
+    })
 }
 
-fn layer_with_least_of_digit(digit: u32, data: &[Vec<u32>]) -> usize {
-    let mut best_layer = 0;
-    let mut best_count = u32::max_value();
-    for (i, layer) in data.iter().enumerate() {
-        let count = count_digit(digit, layer);
-        if count < best_count {
-            best_layer = i;
-            best_count = count;
-        }
-    }
-    best_layer
+fn challenge_1() {
+    let image = Image::decode(&read_data("input"), WIDTH, HEIGHT).unwrap();
+    println!("Challenge 1: Solution = {}", solve_challenge_1(&image));
 }
 
-fn count_digit(digit: u32, values: &[u32]) -> u32 {
-    let mut count = 0;
-    for v in values {
-        if *v == digit {
-            count += 1;
-        }
-    }
-    count
+fn challenge_2() {
+    let image = Image::decode(&read_data("input"), WIDTH, HEIGHT).unwrap();
+    println!("Challenge 2:");
+    print!("{}", image.to_text());
 }
 
-fn flatten(layers: &[Vec<u32>]) -> Vec<u32> {
-    let mut out = layers[0].clone();
-    for layer in layers.iter().skip(1) {
-        for (v, w) in out.iter_mut().zip(layer) {
-            if *v == TRANSPARENT {
-                *v = *w;
-            }
-        }
-    }
-    out
+fn solve_challenge_1(image: &Image) -> usize {
+    let count_digit = |digit, layer: &[u8]| layer.iter().filter(|&&v| v == digit).count();
+    let layer = image
+        .layers
+        .iter()
+        .min_by_key(|layer| count_digit(0, layer))
+        .unwrap();
+    count_digit(1, layer) * count_digit(2, layer)
 }
 
-fn to_string(image: &[u32], width: usize, height: usize) -> String {
-    let mut image = image.iter();
-    let mut out: Vec<&str> = Vec::new();
-    for _ in 0..height {
-        for _ in 0..width {
-            out.push(match *image.next().unwrap() {
-                BLACK => " ",
-                WHITE => "█",
-                _ => unreachable!(),
-            });
-        }
-        out.push("\n");
-    }
-    out.join("")
+fn read_data(filename: &str) -> String {
+    fs::read_to_string(filename).unwrap()
 }
 
 #[cfg(test)]
@@ -113,60 +105,29 @@ mod test_day_8 {
 
     #[test]
     fn reads_data() {
-        let data = read_data();
-        assert_eq!(data.len(), 15_000);
-        assert_eq!(data[..10], [1, 2, 2, 2, 2, 2, 2, 2, 2, 0]);
+        let data = read_data("input");
+        assert_eq!(data.trim().len(), 15_000);
+        assert!(data.starts_with("1222222220"));
     }
 
     #[test]
     fn solves_challenge_1() {
-        let data = vec![
-            1, 1, 2, // layer 1,
-            2, 2, 2, //
-            1, 2, 2, // layer 2
-            2, 2, 0, //
-        ];
-        assert_eq!(solve_challenge_1(data, 3, 2), 8);
-
-        let data = vec![
-            0, 0, 1, 1, // layer 1,
-            1, 1, 1, 2, //
-            1, 1, 1, 2, // layer 2
-            2, 2, 2, 0, //
-        ];
-        assert_eq!(solve_challenge_1(data, 4, 2), 12);
-    }
-
-    #[test]
-    fn flattens() {
-        let data = build_layers(
-            vec![
-                0, 2, // layer 1
-                2, 2, //
-                1, 1, // layer 2
-                2, 2, //
-                2, 2, // layer 3
-                1, 2, //
-                0, 0, // layer 4
-                0, 0,
-            ],
-            2,
+        let image = Image::decode(
+            "112222\
+             122220",
+            3,
             2,
-        );
-        let expected = vec![
-            0, 1, //
-            1, 0, //
-        ];
-        assert_eq!(flatten(&data), expected);
-    }
+        )
+        .unwrap();
+        assert_eq!(solve_challenge_1(&image), 8);
 
-    #[test]
-    fn converts_image_to_string() {
-        let image = vec![
-            0, 0, 1, //
-            1, 1, 1, //
-        ];
-        let expected = "  █\n███\n";
-        assert_eq!(to_string(&image, 3, 2), expected);
+        let image = Image::decode(
+            "00111112\
+             11122220",
+            4,
+            2,
+        )
+        .unwrap();
+        assert_eq!(solve_challenge_1(&image), 12);
     }
 }
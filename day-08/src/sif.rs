@@ -0,0 +1,431 @@
+use std::fmt;
+
+pub const BLACK: u8 = 0;
+pub const WHITE: u8 = 1;
+pub const TRANSPARENT: u8 = 2;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    ZeroSize {
+        width: usize,
+        height: usize,
+    },
+    NotADigit {
+        index: usize,
+        found: char,
+    },
+    /// The data doesn't divide into a whole number of layers, or there are none.
+    WrongLength {
+        len: usize,
+        layer_size: usize,
+    },
+    /// The image, with each pixel repeated `scale` times in each direction, has more pixels
+    /// than fit in memory or in an image file.
+    TooLarge {
+        width: usize,
+        height: usize,
+        scale: usize,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::ZeroSize { width, height } => {
+                write!(f, "invalid image size {}x{}", width, height)
+            }
+            DecodeError::NotADigit { index, found } => {
+                write!(
+                    f,
+                    "expected a digit at position {}, found {:?}",
+                    index, found
+                )
+            }
+            DecodeError::WrongLength { len, layer_size } => write!(
+                f,
+                "{} digits is not a positive multiple of the layer size {}",
+                len, layer_size
+            ),
+            DecodeError::TooLarge {
+                width,
+                height,
+                scale,
+            } => write!(
+                f,
+                "image size {}x{} at scale {} is too large",
+                width, height, scale
+            ),
+        }
+    }
+}
+
+/// Maps pixel values to colours, with `None` for transparent values.
+#[derive(Debug, Clone)]
+pub struct Palette {
+    colours: Vec<Option<[u8; 3]>>,
+}
+
+impl Palette {
+    /// Black, white and transparent, as used by the Elves.
+    pub fn standard() -> Self {
+        Self {
+            colours: vec![Some([0, 0, 0]), Some([255, 255, 255]), None],
+        }
+    }
+
+    /// The standard palette followed by red, green, blue, yellow, cyan, magenta and grey, so
+    /// every digit has a meaning.
+    pub fn extended() -> Self {
+        let mut palette = Self::standard();
+        palette.colours.extend(vec![
+            Some([255, 0, 0]),
+            Some([0, 255, 0]),
+            Some([0, 0, 255]),
+            Some([255, 255, 0]),
+            Some([0, 255, 255]),
+            Some([255, 0, 255]),
+            Some([128, 128, 128]),
+        ]);
+        palette
+    }
+
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "standard" => Some(Self::standard()),
+            "extended" => Some(Self::extended()),
+            _ => None,
+        }
+    }
+
+    /// The colour of a pixel value. Values outside the palette are treated as transparent.
+    pub fn colour(&self, value: u8) -> Option<[u8; 3]> {
+        self.colours.get(value as usize).copied().flatten()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    /// The layers from front to back, each with one value per pixel in row order.
+    pub layers: Vec<Vec<u8>>,
+}
+
+impl Image {
+    pub fn decode(data: &str, width: usize, height: usize) -> Result<Self, DecodeError> {
+        if width == 0 || height == 0 {
+            return Err(DecodeError::ZeroSize { width, height });
+        }
+        let digits = data
+            .trim()
+            .chars()
+            .enumerate()
+            .map(|(index, c)| match c.to_digit(10) {
+                Some(d) => Ok(d as u8),
+                None => Err(DecodeError::NotADigit { index, found: c }),
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
+        let layer_size = width.checked_mul(height).ok_or(DecodeError::TooLarge {
+            width,
+            height,
+            scale: 1,
+        })?;
+        if digits.is_empty() || digits.len() % layer_size != 0 {
+            return Err(DecodeError::WrongLength {
+                len: digits.len(),
+                layer_size,
+            });
+        }
+        Ok(Self {
+            width,
+            height,
+            layers: digits.chunks(layer_size).map(|l| l.to_vec()).collect(),
+        })
+    }
+
+    /// Stacks the layers, each pixel taking the value of the frontmost layer where it isn't
+    /// transparent in `palette`. Pixels transparent in every layer keep the back value.
+    pub fn compose(&self, palette: &Palette) -> Vec<u8> {
+        let mut image = self.layers[0].clone();
+        for layer in &self.layers[1..] {
+            for (v, w) in image.iter_mut().zip(layer) {
+                if palette.colour(*v).is_none() {
+                    *v = *w;
+                }
+            }
+        }
+        image
+    }
+
+    /// Renders the standard composition with white pixels as blocks. Other visible values are
+    /// shown as digits.
+    pub fn to_text(&self) -> String {
+        let image = self.compose(&Palette::standard());
+        let mut out = String::new();
+        for row in image.chunks(self.width) {
+            for &v in row {
+                out.push(match v {
+                    BLACK | TRANSPARENT => ' ',
+                    WHITE => '█',
+                    _ => (b'0' + v) as char,
+                });
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    // The width and height after scaling, if every RGBA pixel fits in memory and each side
+    // fits in a PNG header.
+    fn scaled_size(&self, scale: usize) -> Result<(usize, usize), DecodeError> {
+        let side = |len: usize| {
+            len.checked_mul(scale)
+                .filter(|&len| len <= i32::MAX as usize)
+        };
+        match (side(self.width), side(self.height)) {
+            (Some(width), Some(height))
+                if width
+                    .checked_mul(height)
+                    .and_then(|pixels| pixels.checked_mul(4))
+                    .is_some() =>
+            {
+                Ok((width, height))
+            }
+            _ => Err(DecodeError::TooLarge {
+                width: self.width,
+                height: self.height,
+                scale,
+            }),
+        }
+    }
+
+    // The composed image as rows of RGBA pixels, each pixel repeated `scale` times in each
+    // direction.
+    fn rgba_rows(&self, palette: &Palette, scale: usize) -> Vec<Vec<[u8; 4]>> {
+        assert!(scale >= 1, "scale must be at least 1");
+        let image = self.compose(palette);
+        let mut rows = vec![];
+        for row in image.chunks(self.width) {
+            let pixels: Vec<[u8; 4]> = row
+                .iter()
+                .flat_map(|&v| {
+                    let [r, g, b] = palette.colour(v).unwrap_or([0, 0, 0]);
+                    let alpha = if palette.colour(v).is_some() { 255 } else { 0 };
+                    vec![[r, g, b, alpha]; scale]
+                })
+                .collect();
+            for _ in 0..scale {
+                rows.push(pixels.clone());
+            }
+        }
+        rows
+    }
+
+    /// Encodes the composed image as a binary PPM, drawing transparent pixels in
+    /// `background`.
+    pub fn to_ppm(
+        &self,
+        palette: &Palette,
+        scale: usize,
+        background: [u8; 3],
+    ) -> Result<Vec<u8>, DecodeError> {
+        let (width, height) = self.scaled_size(scale)?;
+        let mut ppm = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+        for row in self.rgba_rows(palette, scale) {
+            for [r, g, b, a] in row {
+                ppm.extend_from_slice(&if a == 0 { background } else { [r, g, b] });
+            }
+        }
+        Ok(ppm)
+    }
+
+    /// Encodes the composed image as an RGBA PNG, with transparent pixels fully transparent.
+    pub fn to_png(&self, palette: &Palette, scale: usize) -> Result<Vec<u8>, DecodeError> {
+        let (width, height) = self.scaled_size(scale)?;
+        let mut raw = vec![];
+        for row in self.rgba_rows(palette, scale) {
+            raw.push(0); // No filter.
+            raw.extend(row.iter().flatten());
+        }
+        let mut header = vec![];
+        header.extend_from_slice(&(width as u32).to_be_bytes());
+        header.extend_from_slice(&(height as u32).to_be_bytes());
+        // 8 bits per channel, RGBA, default compression, filtering and no interlacing.
+        header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+        let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+        png_chunk(&mut png, b"IHDR", &header);
+        png_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+        png_chunk(&mut png, b"IEND", &[]);
+        Ok(png)
+    }
+}
+
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+// Wraps data in a zlib stream of uncompressed deflate blocks, which every PNG decoder
+// accepts and keeps the encoder free of dependencies.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = if data.is_empty() {
+        vec![&[]]
+    } else {
+        data.chunks(0xffff).collect()
+    };
+    for (i, block) in blocks.iter().enumerate() {
+        out.push((i == blocks.len() - 1) as u8);
+        let len = block.len() as u16;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + u32::from(byte)) % 65521;
+        b = (b + a) % 65521;
+    }
+    out.extend_from_slice(&(b << 16 | a).to_be_bytes());
+    out
+}
+
+#[cfg(test)]
+mod sif_tests {
+    use super::*;
+
+    #[test]
+    fn decodes_layers() {
+        let image = Image::decode("123456789012\n", 3, 2).unwrap();
+        assert_eq!(
+            image.layers,
+            vec![vec![1, 2, 3, 4, 5, 6], vec![7, 8, 9, 0, 1, 2]]
+        );
+        assert_eq!(
+            Image::decode("12345678901", 3, 2),
+            Err(DecodeError::WrongLength {
+                len: 11,
+                layer_size: 6
+            })
+        );
+        assert_eq!(
+            Image::decode("12x4", 2, 2).unwrap_err().to_string(),
+            "expected a digit at position 2, found 'x'"
+        );
+        assert!(Image::decode("", 2, 2).is_err());
+        assert!(Image::decode("1234", 0, 2).is_err());
+    }
+
+    #[test]
+    fn composes_layers() {
+        let image = Image::decode("0222112222120000", 2, 2).unwrap();
+        assert_eq!(image.compose(&Palette::standard()), vec![0, 1, 1, 0]);
+        assert_eq!(image.to_text(), " █\n█ \n");
+
+        // Values beyond the standard palette are see-through unless the palette has them.
+        let image = Image::decode("23451111", 2, 2).unwrap();
+        assert_eq!(image.compose(&Palette::standard()), vec![1, 1, 1, 1]);
+        assert_eq!(image.compose(&Palette::extended()), vec![1, 3, 4, 5]);
+        let image = Image::decode("2222", 2, 2).unwrap();
+        assert_eq!(image.compose(&Palette::standard()), vec![2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn exports_ppm() {
+        let image = Image::decode("0123", 2, 2).unwrap();
+        let ppm = image.to_ppm(&Palette::extended(), 1, [9, 9, 9]).unwrap();
+        let mut expected = b"P6\n2 2\n255\n".to_vec();
+        expected.extend_from_slice(&[0, 0, 0, 255, 255, 255, 9, 9, 9, 255, 0, 0]);
+        assert_eq!(ppm, expected);
+
+        let ppm = image.to_ppm(&Palette::standard(), 2, [9, 9, 9]).unwrap();
+        assert!(ppm.starts_with(b"P6\n4 4\n255\n"));
+        assert_eq!(ppm.len(), 11 + 4 * 4 * 3);
+    }
+
+    #[test]
+    fn exports_png() {
+        let image = Image::decode("012", 3, 1).unwrap();
+        let png = image.to_png(&Palette::standard(), 1).unwrap();
+        assert_eq!(
+            png[..8],
+            [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n']
+        );
+        assert_eq!(png[12..16], *b"IHDR");
+        assert_eq!(png[16..24], [0, 0, 0, 3, 0, 0, 0, 1]);
+        // Every PNG ends with the same empty IEND chunk.
+        assert_eq!(
+            png[png.len() - 12..],
+            [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]
+        );
+        let idat = &png[33..png.len() - 12];
+        assert_eq!(idat[4..8], *b"IDAT");
+        // The zlib header, a final stored block of 13 bytes holding the filter type and
+        // pixels, and the Adler-32 checksum.
+        let mut expected = vec![0x78, 0x01, 1, 13, 0, !13, 0xff];
+        expected.extend_from_slice(&[0, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 0]);
+        expected.extend_from_slice(&[0x22, 0xea, 0x04, 0xfc]);
+        assert_eq!(idat[8..idat.len() - 4], expected[..]);
+    }
+
+    #[test]
+    #[should_panic(expected = "scale must be at least 1")]
+    fn refuses_to_scale_to_nothing() {
+        Image::decode("012", 3, 1)
+            .unwrap()
+            .to_png(&Palette::standard(), 0)
+            .unwrap();
+    }
+
+    #[test]
+    fn refuses_oversized_images() {
+        assert_eq!(
+            Image::decode("1", usize::MAX, 2),
+            Err(DecodeError::TooLarge {
+                width: usize::MAX,
+                height: 2,
+                scale: 1
+            })
+        );
+        let image = Image::decode("012", 3, 1).unwrap();
+        let too_large = |scale| DecodeError::TooLarge {
+            width: 3,
+            height: 1,
+            scale,
+        };
+        assert_eq!(
+            image.to_png(&Palette::standard(), usize::MAX / 2),
+            Err(too_large(usize::MAX / 2))
+        );
+        assert_eq!(
+            image.to_ppm(&Palette::standard(), 1 << 30, [0, 0, 0]),
+            Err(too_large(1 << 30))
+        );
+    }
+
+    #[test]
+    fn calculates_checksums() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(zlib_stored(b"Wikipedia")[16..], [0x11, 0xe6, 0x03, 0x98]);
+    }
+}
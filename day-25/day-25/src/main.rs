@@ -1,21 +1,21 @@
-use intcode_computer::Computer;
+use intcode_computer::session::SessionRecorder;
+use intcode_computer::{batch, Computer, RunResult};
 use std::io;
 
 const WIDTH: usize = 50;
 const HEIGHT: usize = 20;
 
 fn main() -> io::Result<()> {
-    let mut computer = Computer::load_from_file("input");
+    let mut session = SessionRecorder::new(Computer::load_from_file("input"), true);
     let mut map = Map::new();
-    let mut history: Vec<String> = Vec::new();
-    run_commands_from_file(&mut computer, &mut map, &mut history);
-    // run_interactive(&mut computer, &mut map, &mut history)?;
-    // find_combination_of_items_to_pass_floor(&mut computer);
+    run_commands_from_file(&mut session, &mut map);
+    // run_interactive(&mut session, &mut map)?;
+    // find_combination_of_items_to_pass_floor(session.computer());
     Ok(())
 }
 
 #[allow(dead_code)]
-fn find_combination_of_items_to_pass_floor(computer: &mut Computer) {
+fn find_combination_of_items_to_pass_floor(computer: &Computer) {
     let items = [
         "mutex",
         "ornament",
@@ -25,28 +25,34 @@ fn find_combination_of_items_to_pass_floor(computer: &mut Computer) {
         "shell",
         "klein bottle",
     ];
-    for item_specification in 0..2usize.pow(items.len() as u32) {
-        let mut commands = String::new();
-        for (i, item) in items.iter().enumerate() {
-            let keep = (item_specification >> i) & 1 == 1;
-            if !keep {
-                commands.push_str(&format!("drop {}\n", item));
+    let commands: Vec<String> = (0..2usize.pow(items.len() as u32))
+        .map(|item_specification| {
+            let mut commands = String::new();
+            for (i, item) in items.iter().enumerate() {
+                let keep = (item_specification >> i) & 1 == 1;
+                if !keep {
+                    commands.push_str(&format!("drop {}\n", item));
+                }
             }
-        }
-        commands.push_str("south\n");
-
-        let mut computer = computer.clone();
-        let input = commands.chars().map(|c| c as i64).collect();
-        let (output, _) = computer.run(input);
-        let output: String = output.iter().map(|&c| c as u8 as char).collect();
-        if !output.contains("ejected") {
-            print!("{}", commands);
-            break;
-        }
+            commands.push_str("south\n");
+            commands
+        })
+        .collect();
+    let inputs: Vec<Vec<i64>> = commands
+        .iter()
+        .map(|c| c.chars().map(|c| c as i64).collect())
+        .collect();
+    let passes = |result: &RunResult| {
+        let output: String = result.output.iter().map(|&c| c as u8 as char).collect();
+        !output.contains("ejected")
+    };
+    let results = batch::run_batch_until(computer, &inputs, passes);
+    if let Some(i) = results.iter().position(|r| r.as_ref().is_some_and(passes)) {
+        print!("{}", commands[i]);
     }
 }
 
-fn run_commands_from_file(computer: &mut Computer, map: &mut Map, history: &mut Vec<String>) {
+fn run_commands_from_file(session: &mut SessionRecorder, map: &mut Map) {
     let commands: Vec<String> = std::fs::read_to_string("commands")
         .unwrap()
         .lines()
@@ -57,10 +63,9 @@ fn run_commands_from_file(computer: &mut Computer, map: &mut Map, history: &mut
         })
         .collect();
     for command in commands {
-        map.process(&command.trim());
-        history.push(command.clone());
+        map.process(command.trim());
         let input = command.chars().map(|c| c as i64).collect();
-        let (output, _) = computer.run(input);
+        let output = session.run(input).unwrap().output;
         let output: String = output.iter().map(|&c| c as u8 as char).collect();
         print!("{}", map.draw());
         print!("{}", output);
@@ -68,14 +73,10 @@ fn run_commands_from_file(computer: &mut Computer, map: &mut Map, history: &mut
 }
 
 #[allow(dead_code)]
-fn run_interactive(
-    computer: &mut Computer,
-    map: &mut Map,
-    history: &mut Vec<String>,
-) -> io::Result<()> {
+fn run_interactive(session: &mut SessionRecorder, map: &mut Map) -> io::Result<()> {
     let mut input: Vec<i64> = vec![];
     loop {
-        let (output, _) = computer.run(input);
+        let output = session.run(input).unwrap().output;
         let output: String = output.iter().map(|&c| c as u8 as char).collect();
         print!("{}", map.draw());
         print!("{}", output);
@@ -84,7 +85,7 @@ fn run_interactive(
         loop {
             command = String::new();
             io::stdin().read_line(&mut command)?;
-            command = String::from(match command.trim().as_ref() {
+            command = String::from(match command.trim() {
                 "n" => "north\n",
                 "s" => "south\n",
                 "e" => "east\n",
@@ -95,17 +96,21 @@ fn run_interactive(
             });
             if command == "history\n" {
                 println!("Command History:");
-                for cmd in history.clone() {
-                    print!("{}", cmd);
+                for cmd in session.session().inputs() {
+                    println!("{}", cmd);
                 }
                 continue;
             }
-            map.process(&command.trim());
+            if command == "save\n" {
+                session.session().save("session")?;
+                println!("Session saved");
+                continue;
+            }
+            map.process(command.trim());
             if command != "ignore\n" {
                 break;
             }
         }
-        history.push(command.clone());
         input = command.chars().map(|c| c as i64).collect();
     }
 }
@@ -0,0 +1,114 @@
+use crate::{Computer, RunResult};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Runs a clone of `base` on each input vector across all available cores. Results are
+/// returned in the same order as `inputs`.
+pub fn run_batch(base: &Computer, inputs: &[Vec<i64>]) -> Vec<RunResult> {
+    run_batch_until(base, inputs, |_| false)
+        .into_iter()
+        .map(|result| result.unwrap())
+        .collect()
+}
+
+/// Like `run_batch` but stops starting new runs once `stop` returns true for a result.
+/// Runs that were skipped are `None`. Inputs are started in order, so every input before
+/// the first one satisfying `stop` is guaranteed to have a result.
+pub fn run_batch_until<F>(base: &Computer, inputs: &[Vec<i64>], stop: F) -> Vec<Option<RunResult>>
+where
+    F: Fn(&RunResult) -> bool + Sync,
+{
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    run_batch_on_threads(base, inputs, threads, stop)
+}
+
+pub fn run_batch_on_threads<F>(
+    base: &Computer,
+    inputs: &[Vec<i64>],
+    threads: usize,
+    stop: F,
+) -> Vec<Option<RunResult>>
+where
+    F: Fn(&RunResult) -> bool + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let stopped = AtomicBool::new(false);
+    let results = Mutex::new(vec![None; inputs.len()]);
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1).min(inputs.len()) {
+            scope.spawn(|| loop {
+                if stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                if i >= inputs.len() {
+                    break;
+                }
+                let result = base.clone().run(inputs[i].clone());
+                if stop(&result) {
+                    stopped.store(true, Ordering::SeqCst);
+                }
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+    results.into_inner().unwrap()
+}
+
+#[cfg(test)]
+mod batch_tests {
+    use super::*;
+
+    fn doubler() -> Computer {
+        Computer::load(vec![
+            3,    // 0: input
+            9,    // 1: ... to @9
+            1002, // 2: multiply
+            9,    // 3: @9
+            2,    // 4: 2
+            9,    // 5: ... to @9
+            4,    // 6: output
+            9,    // 7: @9
+            99,   // 8: halt
+            0,    // 9
+        ])
+    }
+
+    #[test]
+    fn returns_results_in_input_order() {
+        let inputs: Vec<Vec<i64>> = (0..100).map(|i| vec![i]).collect();
+        let results = run_batch(&doubler(), &inputs);
+        for (i, result) in results.into_iter().enumerate() {
+            assert_eq!(result.output, vec![2 * i as i64]);
+            assert!(result.is_halted());
+        }
+    }
+
+    #[test]
+    fn does_not_modify_base_computer() {
+        let base = doubler();
+        run_batch(&base, &[vec![1], vec![2]]);
+        assert!(!base.clone().run(vec![]).is_halted());
+    }
+
+    #[test]
+    fn stops_starting_runs_once_predicate_is_satisfied() {
+        let inputs: Vec<Vec<i64>> = (0..100).map(|i| vec![i]).collect();
+        let results = run_batch_on_threads(&doubler(), &inputs, 1, |r| r.output[0] == 20);
+        assert!(results[..=10].iter().all(|r| r.is_some()));
+        assert!(results[11..].iter().all(|r| r.is_none()));
+    }
+
+    #[test]
+    fn every_input_before_the_first_match_has_a_result() {
+        let inputs: Vec<Vec<i64>> = (0..1000).map(|i| vec![i]).collect();
+        let results = run_batch_on_threads(&doubler(), &inputs, 4, |r| r.output[0] >= 500);
+        let first = results
+            .iter()
+            .position(|r| r.as_ref().is_some_and(|r| r.output[0] >= 500))
+            .unwrap();
+        assert_eq!(first, 250);
+        assert!(results[..first].iter().all(|r| r.is_some()));
+    }
+}
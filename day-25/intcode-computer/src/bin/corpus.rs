@@ -0,0 +1,16 @@
+use intcode_computer::corpus::{executors, load, validate};
+use std::path::Path;
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 2 {
+        eprintln!("usage: {} [corpus-dir]", args[0]);
+        std::process::exit(1);
+    }
+    let dir = args.get(1).map_or("corpus", |s| s.as_str());
+    let report = validate(&load(Path::new(dir)), &executors());
+    print!("{}", report);
+    if !report.passed() {
+        std::process::exit(1);
+    }
+}
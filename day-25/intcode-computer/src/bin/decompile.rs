@@ -0,0 +1,16 @@
+use intcode_computer::decompile::decompile;
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 2 {
+        eprintln!("usage: {} <program>", args[0]);
+        std::process::exit(1);
+    }
+    let program: Vec<i64> = std::fs::read_to_string(&args[1])
+        .unwrap()
+        .trim()
+        .split(',')
+        .map(|s| s.parse().unwrap())
+        .collect();
+    print!("{}", decompile(&program));
+}
@@ -0,0 +1,22 @@
+use intcode_computer::lint::{lint, Severity};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 2 {
+        eprintln!("usage: {} <program>", args[0]);
+        std::process::exit(1);
+    }
+    let program: Vec<i64> = std::fs::read_to_string(&args[1])
+        .unwrap()
+        .trim()
+        .split(',')
+        .map(|s| s.parse().unwrap())
+        .collect();
+    let diagnostics = lint(&program);
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic);
+    }
+    if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+        std::process::exit(1);
+    }
+}
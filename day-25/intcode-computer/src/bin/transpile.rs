@@ -0,0 +1,16 @@
+use intcode_computer::transpile::transpile;
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 3 {
+        eprintln!("usage: {} <program> <struct name>", args[0]);
+        std::process::exit(1);
+    }
+    let program: Vec<i64> = std::fs::read_to_string(&args[1])
+        .unwrap()
+        .trim()
+        .split(',')
+        .map(|s| s.parse().unwrap())
+        .collect();
+    print!("{}", transpile(&program, &args[2]));
+}
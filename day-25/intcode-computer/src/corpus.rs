@@ -0,0 +1,238 @@
+use crate::optimize::optimize;
+use crate::Computer;
+use std::fmt;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+/// A program run with one input and its expected results. The corpus directory holds a
+/// directory per program containing a `program` file and, for each case, `<case>.input`,
+/// `<case>.output` and optionally `<case>.memory`, which gives the expected start of memory
+/// once the program stops. All files are comma separated values.
+#[derive(Clone, Debug)]
+pub struct Case {
+    pub name: String,
+    pub program: Vec<i64>,
+    pub input: Vec<i64>,
+    pub output: Vec<i64>,
+    pub memory: Option<Vec<i64>>,
+}
+
+/// What an executor produced. Executors that don't preserve the memory layout of the
+/// program leave `memory` empty and are only checked on their output.
+pub struct Execution {
+    pub output: Vec<i64>,
+    pub memory: Option<Vec<i64>>,
+}
+
+pub type Executor = Box<dyn Fn(&[i64], Vec<i64>) -> Execution>;
+
+fn read_values(path: &Path) -> Vec<i64> {
+    fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {}", path.display(), e))
+        .trim()
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("invalid value {:?} in {}", s, path.display()))
+        })
+        .collect()
+}
+
+fn sorted_entries(dir: &Path) -> Vec<std::path::PathBuf> {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("failed to read {}: {}", dir.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    entries.sort();
+    entries
+}
+
+pub fn load(dir: &Path) -> Vec<Case> {
+    let mut cases = vec![];
+    for program_dir in sorted_entries(dir).into_iter().filter(|p| p.is_dir()) {
+        let program = read_values(&program_dir.join("program"));
+        let program_name = program_dir.file_name().unwrap().to_string_lossy();
+        for path in sorted_entries(&program_dir) {
+            if path.extension().is_none_or(|e| e != "input") {
+                continue;
+            }
+            let memory = path.with_extension("memory");
+            cases.push(Case {
+                name: format!(
+                    "{}/{}",
+                    program_name,
+                    path.file_stem().unwrap().to_string_lossy()
+                ),
+                program: program.clone(),
+                input: read_values(&path),
+                output: read_values(&path.with_extension("output")),
+                memory: if memory.exists() {
+                    Some(read_values(&memory))
+                } else {
+                    None
+                },
+            });
+        }
+    }
+    cases
+}
+
+/// The executors built into this crate.
+pub fn executors() -> Vec<(&'static str, Executor)> {
+    vec![
+        (
+            "computer",
+            Box::new(|program: &[i64], input| {
+                let mut computer = Computer::load(program.to_vec());
+                let output = computer.run(input).output;
+                Execution {
+                    output,
+                    memory: Some(computer.memory().to_vec()),
+                }
+            }),
+        ),
+        (
+            "outputs",
+            Box::new(|program: &[i64], input| {
+                let mut computer = Computer::load(program.to_vec());
+                let output = computer.outputs(input).collect();
+                Execution {
+                    output,
+                    memory: Some(computer.memory().to_vec()),
+                }
+            }),
+        ),
+        (
+            "optimized",
+            Box::new(|program: &[i64], input| Execution {
+                output: Computer::load(optimize(program)).run(input).output,
+                memory: None,
+            }),
+        ),
+    ]
+}
+
+fn check(case: &Case, executor: &Executor) -> Result<(), String> {
+    let execution = panic::catch_unwind(AssertUnwindSafe(|| {
+        executor(&case.program, case.input.clone())
+    }))
+    .map_err(|_| "panicked".to_owned())?;
+    if execution.output != case.output {
+        return Err(format!(
+            "expected output {:?}, got {:?}",
+            case.output, execution.output
+        ));
+    }
+    if let (Some(expected), Some(memory)) = (&case.memory, &execution.memory) {
+        let actual = &memory[..expected.len().min(memory.len())];
+        if actual != &expected[..] {
+            return Err(format!("expected memory {:?}, got {:?}", expected, actual));
+        }
+    }
+    Ok(())
+}
+
+pub struct Report {
+    executors: Vec<String>,
+    rows: Vec<(String, Vec<Result<(), String>>)>,
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.rows
+            .iter()
+            .all(|(_, results)| results.iter().all(|r| r.is_ok()))
+    }
+}
+
+/// A table with a row per case and a column per executor, followed by the reason for
+/// each failure.
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self
+            .rows
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or(0);
+        let mut line = format!("{:width$}", "case", width = width);
+        for executor in &self.executors {
+            line.push_str(&format!("  {}", executor));
+        }
+        writeln!(f, "{}", line)?;
+        for (name, results) in &self.rows {
+            let mut line = format!("{:width$}", name, width = width);
+            for (executor, result) in self.executors.iter().zip(results) {
+                let cell = if result.is_ok() { "pass" } else { "FAIL" };
+                line.push_str(&format!("  {:width$}", cell, width = executor.len()));
+            }
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        for (name, results) in &self.rows {
+            for (executor, result) in self.executors.iter().zip(results) {
+                if let Err(reason) = result {
+                    writeln!(f, "{} failed {}: {}", executor, name, reason)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn validate(cases: &[Case], executors: &[(&str, Executor)]) -> Report {
+    Report {
+        executors: executors.iter().map(|(name, _)| name.to_string()).collect(),
+        rows: cases
+            .iter()
+            .map(|case| {
+                let results = executors.iter().map(|(_, e)| check(case, e)).collect();
+                (case.name.clone(), results)
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod corpus_tests {
+    use super::*;
+
+    #[test]
+    fn builtin_executors_pass_corpus() {
+        let cases = load(Path::new("corpus"));
+        assert!(!cases.is_empty());
+        let report = validate(&cases, &executors());
+        assert!(report.passed(), "\n{}", report);
+    }
+
+    #[test]
+    fn reports_failures() {
+        let cases = load(Path::new("corpus"));
+        let cases: Vec<Case> = cases
+            .into_iter()
+            .filter(|c| c.name.starts_with("day-02-example/") || c.name == "day-09-quine/run")
+            .collect();
+        let broken: Executor = Box::new(|program: &[i64], input| {
+            let mut computer = Computer::load(program.to_vec());
+            let mut output = computer.run(input).output;
+            output.truncate(1);
+            Execution {
+                output,
+                memory: Some(vec![0]),
+            }
+        });
+        let report = validate(&cases, &[("broken", broken)]);
+        assert!(!report.passed());
+        assert_eq!(
+            report.to_string(),
+            "case                broken
+day-02-example/run  FAIL
+day-09-quine/run    FAIL
+broken failed day-02-example/run: expected memory [3500, 9, 10, 70, 2, 3, 11, 0, 99, 30, 40, 50], got [0]
+broken failed day-09-quine/run: expected output [109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99], got [109]
+"
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use crate::disassemble;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BranchCount {
+    pub taken: u64,
+    pub not_taken: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Coverage {
+    hits: BTreeMap<usize, u64>,
+    branches: BTreeMap<usize, BranchCount>,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_instruction(&mut self, addr: usize) {
+        *self.hits.entry(addr).or_insert(0) += 1;
+    }
+
+    pub fn record_branch(&mut self, addr: usize, taken: bool) {
+        let count = self.branches.entry(addr).or_default();
+        if taken {
+            count.taken += 1;
+        } else {
+            count.not_taken += 1;
+        }
+    }
+
+    pub fn hits(&self, addr: usize) -> u64 {
+        *self.hits.get(&addr).unwrap_or(&0)
+    }
+
+    pub fn branch(&self, addr: usize) -> Option<&BranchCount> {
+        self.branches.get(&addr)
+    }
+
+    pub fn executed_addresses(&self) -> impl Iterator<Item = usize> + '_ {
+        self.hits.keys().copied()
+    }
+
+    pub fn merge(&mut self, other: &Coverage) {
+        for (&addr, &count) in &other.hits {
+            *self.hits.entry(addr).or_insert(0) += count;
+        }
+        for (&addr, count) in &other.branches {
+            let total = self.branches.entry(addr).or_default();
+            total.taken += count.taken;
+            total.not_taken += count.not_taken;
+        }
+    }
+
+    /// Produces a listing of `program` with the hit count of each executed instruction in the
+    /// left column. Words that were never executed are listed as raw data.
+    pub fn listing(&self, program: &[i64]) -> String {
+        let mut out = String::new();
+        let mut addr = 0;
+        while addr < program.len() {
+            let hits = self.hits(addr);
+            let decoded = if hits > 0 {
+                disassemble(program, addr)
+            } else {
+                None
+            };
+            match decoded {
+                Some((text, len)) => {
+                    out.push_str(&format!("{:>8} {:>5}: {}", hits, addr, text));
+                    if let Some(branch) = self.branch(addr) {
+                        out.push_str(&format!(
+                            "  [taken {}, not taken {}]",
+                            branch.taken, branch.not_taken
+                        ));
+                    }
+                    out.push('\n');
+                    addr += len;
+                }
+                None => {
+                    out.push_str(&format!("{:>8} {:>5}: {}\n", "-", addr, program[addr]));
+                    addr += 1;
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::inconsistent_digit_grouping)]
+mod coverage_tests {
+    use super::*;
+    use crate::Computer;
+
+    fn program() -> Vec<i64> {
+        vec![
+            3,     // 0: input
+            11,    // 1: ... to @11
+            10_05, // 2: jump if true
+            11,    // 3: @11
+            7,     // 4: to @7
+            1_04,  // 5: output
+            0,     // 6: 0
+            1_04,  // 7: output
+            1,     // 8: 1
+            99,    // 9: halt
+            0,     // 10
+            0,     // 11
+        ]
+    }
+
+    #[test]
+    fn records_executed_addresses_and_branches() {
+        let mut computer = Computer::load(program());
+        computer.enable_coverage();
+        computer.run(vec![1]);
+        let coverage = computer.coverage().unwrap();
+        assert_eq!(
+            coverage.executed_addresses().collect::<Vec<_>>(),
+            vec![0, 2, 7, 9]
+        );
+        assert_eq!(coverage.hits(5), 0);
+        assert_eq!(
+            coverage.branch(2),
+            Some(&BranchCount {
+                taken: 1,
+                not_taken: 0
+            })
+        );
+    }
+
+    #[test]
+    fn does_not_count_instructions_waiting_for_input() {
+        let mut computer = Computer::load(program());
+        computer.enable_coverage();
+        computer.run(vec![]);
+        computer.run(vec![0]);
+        assert_eq!(computer.coverage().unwrap().hits(0), 1);
+    }
+
+    #[test]
+    fn does_not_record_without_being_enabled() {
+        let mut computer = Computer::load(program());
+        computer.run(vec![1]);
+        assert!(computer.coverage().is_none());
+    }
+
+    #[test]
+    fn merges_coverage_from_many_runs() {
+        let mut merged = Coverage::new();
+        for input in &[0, 1, 2] {
+            let mut computer = Computer::load(program());
+            computer.enable_coverage();
+            computer.run(vec![*input]);
+            merged.merge(computer.coverage().unwrap());
+        }
+        assert_eq!(merged.hits(0), 3);
+        assert_eq!(merged.hits(5), 1);
+        assert_eq!(merged.hits(7), 3);
+        assert_eq!(
+            merged.branch(2),
+            Some(&BranchCount {
+                taken: 2,
+                not_taken: 1
+            })
+        );
+    }
+
+    #[test]
+    fn produces_annotated_listing() {
+        let mut computer = Computer::load(program());
+        computer.enable_coverage();
+        computer.run(vec![1]);
+        let listing = computer.coverage().unwrap().listing(&program());
+        let expected = "       1     0: in @11
+       1     2: jt @11, 7  [taken 1, not taken 0]
+       -     5: 104
+       -     6: 0
+       1     7: out 1
+       1     9: hlt
+       -    10: 0
+       -    11: 0
+";
+        assert_eq!(listing, expected);
+    }
+}
@@ -0,0 +1,979 @@
+use crate::{decode, Mode, Opcode};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Cells referenced at least this many times are given a variable name.
+const NAMED_CELL_REFERENCES: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Param {
+    Imm(i64),
+    Pos(i64),
+    Rel(i64),
+}
+
+impl Param {
+    pub(crate) fn new(param: &(Mode, i64)) -> Self {
+        match param {
+            (Mode::Immediate, v) => Param::Imm(*v),
+            (Mode::Position, v) => Param::Pos(*v),
+            (Mode::Relative, v) => Param::Rel(*v),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Kind {
+    Binary(&'static str, Param, Param, Param),
+    Input(Param),
+    Output(Param),
+    AdjustBase(Param),
+    Call(usize),
+    Jump(Param),
+    Branch(Param, bool, Param),
+    Nop,
+    Halt,
+    Invalid(i64),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Inst {
+    pub kind: Kind,
+    pub next: usize,
+}
+
+impl Inst {
+    pub(crate) fn successors(&self) -> Vec<usize> {
+        match self.kind {
+            Kind::Jump(Param::Imm(t)) if t >= 0 => vec![t as usize],
+            Kind::Branch(_, _, Param::Imm(t)) if t >= 0 => vec![t as usize, self.next],
+            Kind::Jump(_) | Kind::Halt | Kind::Invalid(_) => vec![],
+            _ => vec![self.next],
+        }
+    }
+}
+
+pub(crate) fn classify(program: &[i64], addr: usize) -> Inst {
+    let invalid = Inst {
+        kind: Kind::Invalid(*program.get(addr).unwrap_or(&0)),
+        next: addr + 1,
+    };
+    let (opcode, params) = match decode(program, addr) {
+        Some(decoded) => decoded,
+        None => return invalid,
+    };
+    let p: Vec<Param> = params.iter().map(Param::new).collect();
+    let next = addr + p.len() + 1;
+    let kind = match opcode {
+        Opcode::Add | Opcode::Multiply | Opcode::LessThan | Opcode::Equals => {
+            if let Some(call) = call_at(program, &opcode, &p, next) {
+                return call;
+            }
+            let op = match opcode {
+                Opcode::Add => "+",
+                Opcode::Multiply => "*",
+                Opcode::LessThan => "<",
+                _ => "==",
+            };
+            Kind::Binary(op, p[0], p[1], p[2])
+        }
+        Opcode::Input => Kind::Input(p[0]),
+        Opcode::Output => Kind::Output(p[0]),
+        Opcode::AdjustRelativeBase => Kind::AdjustBase(p[0]),
+        Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+            let if_true = matches!(opcode, Opcode::JumpIfTrue);
+            match p[0] {
+                Param::Imm(v) if (v != 0) == if_true => Kind::Jump(p[1]),
+                Param::Imm(_) => Kind::Nop,
+                cond => Kind::Branch(cond, if_true, p[1]),
+            }
+        }
+        Opcode::Halt => Kind::Halt,
+    };
+    match kind {
+        Kind::Binary(_, _, _, Param::Imm(_)) | Kind::Input(Param::Imm(_)) => invalid,
+        _ => Inst { kind, next },
+    }
+}
+
+// Calls store the return address at @rb+0 and then jump unconditionally to the function:
+//     add 7, 0, @rb+0 / jt 1, f
+fn call_at(program: &[i64], opcode: &Opcode, p: &[Param], next: usize) -> Option<Inst> {
+    let identity = match opcode {
+        Opcode::Add => 0,
+        Opcode::Multiply => 1,
+        _ => return None,
+    };
+    let ret = match (p[0], p[1], p[2]) {
+        (Param::Imm(r), Param::Imm(i), Param::Rel(0)) if i == identity => r,
+        (Param::Imm(i), Param::Imm(r), Param::Rel(0)) if i == identity => r,
+        _ => return None,
+    };
+    let jump = classify(program, next);
+    match jump.kind {
+        Kind::Jump(Param::Imm(target)) if target >= 0 && ret == jump.next as i64 => Some(Inst {
+            kind: Kind::Call(target as usize),
+            next: jump.next,
+        }),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operand {
+    Const(i64),
+    Cell(i64),
+    // Offset from the relative base on entry to the function.
+    Frame(i64),
+    // Offset from a relative base that could not be tracked.
+    Relative(i64),
+}
+
+fn operand(param: Param, delta: Option<i64>) -> Operand {
+    match (param, delta) {
+        (Param::Imm(v), _) => Operand::Const(v),
+        (Param::Pos(v), _) => Operand::Cell(v),
+        (Param::Rel(k), Some(d)) => Operand::Frame(k + d),
+        (Param::Rel(k), None) => Operand::Relative(k),
+    }
+}
+
+#[derive(Debug)]
+enum Value {
+    Copy(Operand),
+    Neg(Operand),
+    Binary(&'static str, Operand, Operand),
+    Input,
+}
+
+fn simplify(op: &'static str, a: Operand, b: Operand) -> Value {
+    match (op, a, b) {
+        ("+", Operand::Const(x), Operand::Const(y)) => Value::Copy(Operand::Const(x + y)),
+        ("*", Operand::Const(x), Operand::Const(y)) => Value::Copy(Operand::Const(x * y)),
+        ("+", v, Operand::Const(0)) | ("+", Operand::Const(0), v) => Value::Copy(v),
+        ("*", v, Operand::Const(1)) | ("*", Operand::Const(1), v) => Value::Copy(v),
+        ("*", v, Operand::Const(-1)) | ("*", Operand::Const(-1), v) => Value::Neg(v),
+        _ => Value::Binary(op, a, b),
+    }
+}
+
+#[derive(Debug)]
+enum Stmt {
+    Assign(Operand, Value),
+    Output(Operand),
+    AdjustBase(Operand),
+    Call(usize),
+    IndirectBranch(Operand, bool, Operand),
+}
+
+#[derive(Debug)]
+enum Terminator {
+    Goto(usize),
+    Branch(Operand, bool, usize, usize),
+    Return,
+    Halt,
+    Jump(Operand),
+    Invalid(usize, i64),
+}
+
+#[derive(Debug)]
+struct Block {
+    stmts: Vec<Stmt>,
+    terminator: Terminator,
+}
+
+impl Block {
+    fn successors(&self) -> Vec<usize> {
+        match self.terminator {
+            Terminator::Goto(s) => vec![s],
+            Terminator::Branch(_, _, t, n) if t == n => vec![t],
+            Terminator::Branch(_, _, t, n) => vec![t, n],
+            _ => vec![],
+        }
+    }
+}
+
+struct Function {
+    entry: usize,
+    frame: i64,
+    instructions: BTreeMap<usize, Inst>,
+    // Jumps whose target is computed and isn't a return address.
+    unresolved: BTreeSet<usize>,
+    blocks: BTreeMap<usize, Block>,
+}
+
+impl Function {
+    fn new(program: &[i64], entry: usize) -> Self {
+        // Find every reachable instruction along with the relative base at that point,
+        // measured from its value on entry. Conflicting bases are marked unknown.
+        let mut insts: BTreeMap<usize, Inst> = BTreeMap::new();
+        let mut deltas: BTreeMap<usize, Option<i64>> = BTreeMap::new();
+        let mut stack = vec![(entry, Some(0))];
+        while let Some((addr, mut delta)) = stack.pop() {
+            match deltas.get(&addr) {
+                Some(d) if *d == delta || d.is_none() => continue,
+                Some(_) => delta = None,
+                None => {}
+            }
+            deltas.insert(addr, delta);
+            let inst = *insts.entry(addr).or_insert_with(|| classify(program, addr));
+            let after = match inst.kind {
+                Kind::AdjustBase(Param::Imm(n)) => delta.map(|d| d + n),
+                Kind::AdjustBase(_) => None,
+                _ => delta,
+            };
+            for s in inst.successors() {
+                stack.push((s, after));
+            }
+        }
+
+        let frame = match insts[&entry].kind {
+            Kind::AdjustBase(Param::Imm(n)) if n > 0 => n,
+            _ => 0,
+        };
+        let mut leaders: BTreeSet<usize> = BTreeSet::new();
+        leaders.insert(entry);
+        for inst in insts.values() {
+            if let Kind::Jump(_) | Kind::Branch(..) = inst.kind {
+                leaders.extend(inst.successors());
+            }
+        }
+
+        let is_return = |addr: usize| match (insts.get(&addr), deltas.get(&addr)) {
+            (Some(inst), Some(Some(d))) => {
+                matches!(inst.kind, Kind::Jump(Param::Rel(k)) if k + d == 0)
+            }
+            _ => false,
+        };
+        let mut unresolved = BTreeSet::new();
+        let mut blocks = BTreeMap::new();
+        for &leader in &leaders {
+            let mut stmts = vec![];
+            let mut addr = leader;
+            let terminator = loop {
+                let inst = insts[&addr];
+                let delta = deltas[&addr];
+                let op = |p: Param| operand(p, delta);
+                match inst.kind {
+                    Kind::Binary(o, a, b, dest) => {
+                        stmts.push(Stmt::Assign(op(dest), simplify(o, op(a), op(b))))
+                    }
+                    Kind::Input(dest) => stmts.push(Stmt::Assign(op(dest), Value::Input)),
+                    Kind::Output(v) => stmts.push(Stmt::Output(op(v))),
+                    Kind::AdjustBase(v) => {
+                        let prologue = addr == entry && frame > 0;
+                        let epilogue = delta == Some(frame) && is_return(inst.next);
+                        if !prologue && !epilogue {
+                            stmts.push(Stmt::AdjustBase(op(v)));
+                        }
+                    }
+                    Kind::Call(target) => stmts.push(Stmt::Call(target)),
+                    Kind::Branch(cond, if_true, Param::Imm(t)) if t >= 0 => {
+                        break Terminator::Branch(op(cond), if_true, t as usize, inst.next);
+                    }
+                    Kind::Branch(cond, if_true, target) => {
+                        unresolved.insert(addr);
+                        stmts.push(Stmt::IndirectBranch(op(cond), if_true, op(target)))
+                    }
+                    Kind::Jump(Param::Imm(t)) if t >= 0 => break Terminator::Goto(t as usize),
+                    Kind::Jump(_) if is_return(addr) => break Terminator::Return,
+                    Kind::Jump(target) => {
+                        unresolved.insert(addr);
+                        break Terminator::Jump(op(target));
+                    }
+                    Kind::Nop => {}
+                    Kind::Halt => break Terminator::Halt,
+                    Kind::Invalid(word) => break Terminator::Invalid(addr, word),
+                }
+                if leaders.contains(&inst.next) {
+                    break Terminator::Goto(inst.next);
+                }
+                addr = inst.next;
+            };
+            blocks.insert(leader, Block { stmts, terminator });
+        }
+        Self {
+            entry,
+            frame,
+            instructions: insts,
+            unresolved,
+            blocks,
+        }
+    }
+
+    fn callees(&self) -> Vec<usize> {
+        self.blocks
+            .values()
+            .flat_map(|b| &b.stmts)
+            .filter_map(|s| match s {
+                Stmt::Call(target) => Some(*target),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn operands(&self) -> Vec<Operand> {
+        let mut operands = vec![];
+        for block in self.blocks.values() {
+            for stmt in &block.stmts {
+                match stmt {
+                    Stmt::Assign(dest, value) => {
+                        operands.push(*dest);
+                        match value {
+                            Value::Copy(a) | Value::Neg(a) => operands.push(*a),
+                            Value::Binary(_, a, b) => operands.extend(&[*a, *b]),
+                            Value::Input => {}
+                        }
+                    }
+                    Stmt::Output(a) | Stmt::AdjustBase(a) => operands.push(*a),
+                    Stmt::IndirectBranch(a, _, b) => operands.extend(&[*a, *b]),
+                    Stmt::Call(_) => {}
+                }
+            }
+            match block.terminator {
+                Terminator::Branch(a, ..) | Terminator::Jump(a) => operands.push(a),
+                _ => {}
+            }
+        }
+        operands
+    }
+}
+
+/// Dominators and post-dominators of a function's blocks, used to recover loops and
+/// if/else diamonds.
+struct Cfg {
+    succs: BTreeMap<usize, Vec<usize>>,
+    loops: BTreeMap<usize, BTreeSet<usize>>,
+    ipdom: BTreeMap<usize, usize>,
+}
+
+impl Cfg {
+    fn new(function: &Function) -> Self {
+        let succs: BTreeMap<usize, Vec<usize>> = function
+            .blocks
+            .iter()
+            .map(|(&addr, block)| (addr, block.successors()))
+            .collect();
+        let mut preds: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (&addr, ss) in &succs {
+            for &s in ss {
+                preds.entry(s).or_default().push(addr);
+            }
+        }
+        let nodes: BTreeSet<usize> = succs.keys().copied().collect();
+
+        let dom = solve(&nodes, &[function.entry], &preds);
+        let mut loops: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+        for (&u, ss) in &succs {
+            for &h in ss {
+                if dom.get(&u).is_some_and(|d| d.contains(&h)) {
+                    let body = loops
+                        .entry(h)
+                        .or_insert_with(|| vec![h].into_iter().collect());
+                    let mut stack = vec![u];
+                    while let Some(n) = stack.pop() {
+                        if body.insert(n) {
+                            stack.extend(preds.get(&n).into_iter().flatten());
+                        }
+                    }
+                }
+            }
+        }
+
+        let exits: Vec<usize> = succs
+            .iter()
+            .filter(|(_, ss)| ss.is_empty())
+            .map(|(&addr, _)| addr)
+            .collect();
+        let pdom = solve(&nodes, &exits, &succs);
+        let mut ipdom = BTreeMap::new();
+        for (&n, ds) in &pdom {
+            if let Some(&p) = ds
+                .iter()
+                .find(|&&p| p != n && pdom[&p].len() + 1 == ds.len())
+            {
+                ipdom.insert(n, p);
+            }
+        }
+        Self {
+            succs,
+            loops,
+            ipdom,
+        }
+    }
+
+    fn follow(&self, header: usize) -> Option<usize> {
+        let body = &self.loops[&header];
+        let outside = |s: &&usize| !body.contains(s);
+        if let Some(&s) = self.succs[&header].iter().find(outside) {
+            return Some(s);
+        }
+        body.iter()
+            .flat_map(|n| self.succs[n].iter())
+            .filter(outside)
+            .min()
+            .copied()
+    }
+}
+
+// Iteratively computes dominator sets over `edges` (predecessors for dominators,
+// successors for post-dominators). Nodes that are unreachable from `roots` are omitted.
+fn solve(
+    nodes: &BTreeSet<usize>,
+    roots: &[usize],
+    edges: &BTreeMap<usize, Vec<usize>>,
+) -> BTreeMap<usize, BTreeSet<usize>> {
+    let mut reachable: BTreeSet<usize> = roots.iter().copied().collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &n in nodes {
+            if !reachable.contains(&n)
+                && edges
+                    .get(&n)
+                    .into_iter()
+                    .flatten()
+                    .any(|e| reachable.contains(e))
+            {
+                reachable.insert(n);
+                changed = true;
+            }
+        }
+    }
+    let mut sets: BTreeMap<usize, BTreeSet<usize>> = reachable
+        .iter()
+        .map(|&n| {
+            if roots.contains(&n) {
+                (n, vec![n].into_iter().collect())
+            } else {
+                (n, reachable.clone())
+            }
+        })
+        .collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &n in &reachable {
+            if roots.contains(&n) {
+                continue;
+            }
+            let mut set: Option<BTreeSet<usize>> = None;
+            for e in edges.get(&n).into_iter().flatten() {
+                if let Some(other) = sets.get(e) {
+                    set = Some(match set {
+                        Some(s) => s.intersection(other).copied().collect(),
+                        None => other.clone(),
+                    });
+                }
+            }
+            let mut set = set.unwrap_or_default();
+            set.insert(n);
+            if set != sets[&n] {
+                sets.insert(n, set);
+                changed = true;
+            }
+        }
+    }
+    sets
+}
+
+fn function_name(entry: usize) -> String {
+    if entry == 0 {
+        "main".to_owned()
+    } else {
+        format!("f{}", entry)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Region {
+    stop: Option<usize>,
+    header: Option<usize>,
+    follow: Option<usize>,
+}
+
+struct Writer<'a> {
+    function: &'a Function,
+    // Offsets from the relative base on entry that callers pass arguments in.
+    params: &'a BTreeSet<i64>,
+    // The functions found in the program. Calls to anything else are left unresolved.
+    entries: &'a BTreeSet<usize>,
+    cfg: &'a Cfg,
+    names: &'a BTreeMap<i64, String>,
+    labels: BTreeSet<usize>,
+    gotos: BTreeSet<usize>,
+    emitted: BTreeSet<usize>,
+    out: String,
+}
+
+impl<'a> Writer<'a> {
+    fn line(&mut self, depth: usize, s: &str) {
+        self.out.push_str(&"    ".repeat(depth));
+        self.out.push_str(s);
+        self.out.push('\n');
+    }
+
+    fn name(&self, operand: Operand) -> String {
+        match operand {
+            Operand::Const(v) => v.to_string(),
+            Operand::Cell(a) => match self.names.get(&a) {
+                Some(name) => name.clone(),
+                None => format!("mem[{}]", a),
+            },
+            Operand::Frame(0) => "ret".to_owned(),
+            Operand::Frame(o) if o < 0 => format!("frame[{}]", o),
+            // Arguments for a call are named after the callee's parameters, qualified when
+            // they would clash with this function's own.
+            Operand::Frame(o) if o > self.function.frame => {
+                let arg = format!("arg{}", o - self.function.frame - 1);
+                if self.params.is_empty() {
+                    arg
+                } else {
+                    format!("call.{}", arg)
+                }
+            }
+            Operand::Frame(o) if self.params.contains(&o) => format!("arg{}", o - 1),
+            Operand::Frame(o) => format!("local{}", o),
+            Operand::Relative(k) if k < 0 => format!("mem[rb - {}]", -k),
+            Operand::Relative(k) => format!("mem[rb + {}]", k),
+        }
+    }
+
+    fn condition(&self, cond: Operand, if_true: bool) -> String {
+        format!(
+            "{} {} 0",
+            self.name(cond),
+            if if_true { "!=" } else { "==" }
+        )
+    }
+
+    fn stmt(&self, stmt: &Stmt) -> String {
+        match stmt {
+            Stmt::Assign(dest, value) => {
+                let value = match value {
+                    Value::Copy(a) => self.name(*a),
+                    Value::Neg(a) => format!("-{}", self.name(*a)),
+                    Value::Binary("+", a, Operand::Const(b)) if *b < 0 => {
+                        format!("{} - {}", self.name(*a), -b)
+                    }
+                    Value::Binary(op, a, b) => {
+                        format!("{} {} {}", self.name(*a), op, self.name(*b))
+                    }
+                    Value::Input => "input()".to_owned(),
+                };
+                format!("{} = {};", self.name(*dest), value)
+            }
+            Stmt::Output(a) => format!("output({});", self.name(*a)),
+            Stmt::AdjustBase(a) => format!("rb += {};", self.name(*a)),
+            Stmt::Call(target) if self.entries.contains(target) => {
+                format!("{}();", function_name(*target))
+            }
+            Stmt::Call(target) => format!("call *{};", target),
+            Stmt::IndirectBranch(cond, if_true, target) => format!(
+                "if ({}) goto *{};",
+                self.condition(*cond, *if_true),
+                self.name(*target)
+            ),
+        }
+    }
+
+    fn region(&mut self, start: usize, region: Region, depth: usize, entering: bool) {
+        let mut next = Some(start);
+        let mut entering = entering;
+        while let Some(b) = next {
+            if !entering {
+                if Some(b) == region.stop {
+                    return;
+                }
+                if Some(b) == region.follow {
+                    self.line(depth, "break;");
+                    return;
+                }
+                if Some(b) == region.header {
+                    self.line(depth, "continue;");
+                    return;
+                }
+                if self.emitted.contains(&b) {
+                    self.line(depth, &format!("goto L{};", b));
+                    self.gotos.insert(b);
+                    return;
+                }
+                if self.cfg.loops.contains_key(&b) {
+                    let follow = self.cfg.follow(b);
+                    let inner = Region {
+                        stop: None,
+                        header: Some(b),
+                        follow,
+                    };
+                    self.line(depth, "while (true) {");
+                    self.region(b, inner, depth + 1, true);
+                    self.line(depth, "}");
+                    next = follow;
+                    continue;
+                }
+            }
+            entering = false;
+            self.emitted.insert(b);
+            if self.labels.contains(&b) {
+                self.line(depth.saturating_sub(1), &format!("L{}:", b));
+            }
+            let block = &self.function.blocks[&b];
+            for stmt in &block.stmts {
+                let s = self.stmt(stmt);
+                self.line(depth, &s);
+            }
+            match block.terminator {
+                Terminator::Goto(s) => next = Some(s),
+                Terminator::Branch(cond, if_true, taken, fall) => {
+                    let join = self.cfg.ipdom.get(&b).copied();
+                    let inner = Region {
+                        stop: join.or(region.stop),
+                        ..region
+                    };
+                    if Some(taken) == inner.stop {
+                        let c = self.condition(cond, !if_true);
+                        self.line(depth, &format!("if ({}) {{", c));
+                        self.region(fall, inner, depth + 1, false);
+                    } else {
+                        let c = self.condition(cond, if_true);
+                        self.line(depth, &format!("if ({}) {{", c));
+                        self.region(taken, inner, depth + 1, false);
+                        if Some(fall) != inner.stop {
+                            self.line(depth, "} else {");
+                            self.region(fall, inner, depth + 1, false);
+                        }
+                    }
+                    self.line(depth, "}");
+                    next = join;
+                }
+                Terminator::Return => {
+                    self.line(depth, "return;");
+                    return;
+                }
+                Terminator::Halt => {
+                    self.line(depth, "halt();");
+                    return;
+                }
+                Terminator::Jump(target) => {
+                    let s = format!("goto *{};", self.name(target));
+                    self.line(depth, &s);
+                    return;
+                }
+                Terminator::Invalid(addr, word) => {
+                    self.line(depth, &format!("invalid({}); // @{}", word, addr));
+                    return;
+                }
+            }
+        }
+    }
+
+    fn write(
+        function: &Function,
+        params: &BTreeSet<i64>,
+        entries: &BTreeSet<usize>,
+        names: &BTreeMap<i64, String>,
+    ) -> String {
+        let cfg = Cfg::new(function);
+        let mut labels = BTreeSet::new();
+        // The first pass discovers which blocks are reached by a goto so the second pass
+        // can label them.
+        loop {
+            let mut writer = Writer {
+                function,
+                params,
+                entries,
+                cfg: &cfg,
+                names,
+                labels: labels.clone(),
+                gotos: BTreeSet::new(),
+                emitted: BTreeSet::new(),
+                out: String::new(),
+            };
+            let top = Region {
+                stop: None,
+                header: None,
+                follow: None,
+            };
+            writer.line(0, &format!("void {}() {{", function_name(function.entry)));
+            writer.region(function.entry, top, 1, false);
+            writer.line(0, "}");
+            if writer.gotos == labels {
+                return writer.out;
+            }
+            labels = writer.gotos;
+        }
+    }
+}
+
+fn functions(program: &[i64]) -> BTreeMap<usize, Function> {
+    let mut functions: BTreeMap<usize, Function> = BTreeMap::new();
+    let mut pending = vec![0];
+    while let Some(entry) = pending.pop() {
+        if entry >= program.len() || functions.contains_key(&entry) {
+            continue;
+        }
+        let function = Function::new(program, entry);
+        pending.extend(function.callees());
+        functions.insert(entry, function);
+    }
+    functions
+}
+
+/// The instructions statically reachable from address 0, for passes that rewrite code.
+pub(crate) struct Analysis {
+    pub instructions: BTreeMap<usize, Inst>,
+    pub unresolved_jumps: BTreeSet<usize>,
+}
+
+pub(crate) fn analyse(program: &[i64]) -> Analysis {
+    let mut analysis = Analysis {
+        instructions: BTreeMap::new(),
+        unresolved_jumps: BTreeSet::new(),
+    };
+    for function in functions(program).values() {
+        analysis.instructions.extend(&function.instructions);
+        analysis.unresolved_jumps.extend(&function.unresolved);
+    }
+    analysis
+}
+
+/// Recovers structured, C-like pseudocode from `program`. Code reachable from address 0
+/// becomes `main`; every target of the call convention used by the puzzle programs (store
+/// the return address at `@rb+0`, then jump) becomes a separate function whose relative
+/// base adjustments are treated as stack frames. Loops and if/else diamonds are recovered
+/// from the control-flow graph, falling back to gotos for anything else. Frequently used
+/// memory cells are named `varN`, other cells are shown as `mem[N]`. Stack cells that
+/// callers pass values in are named `argN` by position, both in the function and at its
+/// call sites.
+pub fn decompile(program: &[i64]) -> String {
+    let functions = functions(program);
+
+    let mut params: BTreeMap<usize, BTreeSet<i64>> = BTreeMap::new();
+    for function in functions.values() {
+        let outgoing: BTreeSet<i64> = function
+            .operands()
+            .into_iter()
+            .filter_map(|operand| match operand {
+                Operand::Frame(o) if o > function.frame => Some(o - function.frame),
+                _ => None,
+            })
+            .collect();
+        for callee in function.callees() {
+            let frame = match functions.get(&callee) {
+                Some(callee) => callee.frame,
+                None => continue,
+            };
+            params
+                .entry(callee)
+                .or_default()
+                .extend(outgoing.iter().filter(|&&o| o <= frame));
+        }
+    }
+
+    let mut references: BTreeMap<i64, usize> = BTreeMap::new();
+    for function in functions.values() {
+        for operand in function.operands() {
+            if let Operand::Cell(a) = operand {
+                *references.entry(a).or_default() += 1;
+            }
+        }
+    }
+    let names: BTreeMap<i64, String> = references
+        .into_iter()
+        .filter(|&(_, count)| count >= NAMED_CELL_REFERENCES)
+        .map(|(a, _)| (a, format!("var{}", a)))
+        .collect();
+
+    let entries: BTreeSet<usize> = functions.keys().copied().collect();
+    functions
+        .values()
+        .map(|function| {
+            let params = params.get(&function.entry).cloned().unwrap_or_default();
+            Writer::write(function, &params, &entries, &names)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+#[allow(clippy::inconsistent_digit_grouping)]
+mod decompile_tests {
+    use super::*;
+
+    #[test]
+    fn recovers_if_else() {
+        let program: Vec<i64> = vec![
+            3,     // 0: input
+            20,    // 1: ... to @20
+            10_07, // 2: less-than
+            20,    // 3: @20
+            10,    // 4: 10
+            21,    // 5: ... to @21
+            10_05, // 6: jump if true
+            21,    // 7: @21
+            15,    // 8: to @15
+            1_04,  // 9: output
+            1,     // 10: 1
+            11_05, // 11: jump if true
+            1,     // 12: true
+            17,    // 13: to @17
+            0,     // 14
+            1_04,  // 15: output
+            2,     // 16: 2
+            99,    // 17: halt
+            0,     // 18
+            0,     // 19
+            0,     // 20
+            0,     // 21
+        ];
+        assert_eq!(
+            decompile(&program),
+            "void main() {
+    var20 = input();
+    var21 = var20 < 10;
+    if (var21 != 0) {
+        output(2);
+    } else {
+        output(1);
+    }
+    halt();
+}
+"
+        );
+    }
+
+    #[test]
+    fn recovers_loops() {
+        let program: Vec<i64> = vec![
+            3,     // 0: input
+            20,    // 1: ... to @20
+            10_06, // 2: jump if false
+            20,    // 3: @20
+            14,    // 4: to @14
+            4,     // 5: output
+            20,    // 6: @20
+            10_01, // 7: add
+            20,    // 8: @20
+            -1,    // 9: -1
+            20,    // 10: ... to @20
+            11_05, // 11: jump if true
+            1,     // 12: true
+            2,     // 13: to @2
+            99,    // 14: halt
+            0,     // 15
+            0,     // 16
+            0,     // 17
+            0,     // 18
+            0,     // 19
+            0,     // 20
+        ];
+        assert_eq!(
+            decompile(&program),
+            "void main() {
+    var20 = input();
+    while (true) {
+        if (var20 != 0) {
+            output(var20);
+            var20 = var20 - 1;
+            continue;
+        }
+        break;
+    }
+    halt();
+}
+"
+        );
+    }
+
+    #[test]
+    fn recovers_functions_and_stack_frames() {
+        let program: Vec<i64> = vec![
+            1_09,    // 0: adjust relative base
+            100,     // 1: ... by 100
+            3,       // 2: input
+            30,      // 3: ... to @30
+            2_10_01, // 4: add
+            30,      // 5: @30
+            0,       // 6: 0
+            1,       // 7: ... to @rb+1 (argument)
+            2_11_01, // 8: add
+            15,      // 9: return address
+            0,       // 10: 0
+            0,       // 11: ... to @rb+0
+            11_05,   // 12: jump if true
+            1,       // 13: true
+            18,      // 14: to @18
+            2_04,    // 15: output
+            1,       // 16: @rb+1 (result)
+            99,      // 17: halt
+            1_09,    // 18: adjust relative base
+            2,       // 19: ... by 2
+            2_22_02, // 20: multiply
+            -1,      // 21: @rb-1 (argument)
+            -1,      // 22: @rb-1
+            -1,      // 23: ... to @rb-1
+            1_09,    // 24: adjust relative base
+            -2,      // 25: ... by -2
+            21_06,   // 26: jump if false
+            0,       // 27: false
+            0,       // 28: to @rb+0 (return address)
+            0,       // 29
+            0,       // 30
+        ];
+        assert_eq!(
+            decompile(&program),
+            "void main() {
+    var30 = input();
+    arg0 = var30;
+    f18();
+    output(arg0);
+    halt();
+}
+
+void f18() {
+    arg0 = arg0 * arg0;
+    return;
+}
+"
+        );
+    }
+
+    #[test]
+    fn leaves_calls_outside_the_program_unresolved() {
+        let program: Vec<i64> = vec![
+            211_01, // 0: add
+            7,      // 1: return address
+            0,      // 2: 0
+            0,      // 3: ... to @rb+0
+            11_05,  // 4: jump if true
+            1,      // 5: true
+            500,    // 6: to @500
+            99,     // 7: halt
+        ];
+        assert_eq!(
+            decompile(&program),
+            "void main() {
+    call *500;
+    halt();
+}
+"
+        );
+    }
+
+    #[test]
+    fn decompiles_springdroid_program() {
+        let program: Vec<i64> = std::fs::read_to_string("../../day-21/input")
+            .unwrap()
+            .trim()
+            .split(',')
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let source = decompile(&program);
+        assert!(source.starts_with("void main() {\n"));
+        assert!(source.contains("\nvoid f1378() {\n"));
+        assert!(source.contains("input()"));
+        assert!(source.contains("while (true) {"));
+        assert!(source.contains("return;"));
+        // Passing on a parameter to another function.
+        assert!(source.contains("\n    call.arg0 = arg0;\n"));
+    }
+}
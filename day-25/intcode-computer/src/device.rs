@@ -0,0 +1,299 @@
+use std::any::Any;
+use std::collections::VecDeque;
+use std::ops::Range;
+
+/// A peripheral mapped into a range of `Computer` memory. Parameter reads and writes that
+/// land in the range are passed to the device with the offset from the start of the range.
+/// Instructions are always fetched from ordinary memory.
+pub trait Device: DeviceClone + Send + Sync {
+    fn read(&mut self, offset: usize) -> i64;
+
+    fn write(&mut self, offset: usize, value: i64);
+
+    /// The number of cells the device provides, from offset 0.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Called after every instruction the computer executes.
+    fn tick(&mut self) {}
+}
+
+/// Implemented automatically for every `Device` that is `Clone`.
+pub trait DeviceClone {
+    fn clone_device(&self) -> Box<dyn Device>;
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Device + Clone + 'static> DeviceClone for T {
+    fn clone_device(&self) -> Box<dyn Device> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Clone for Box<dyn Device> {
+    fn clone(&self) -> Self {
+        self.clone_device()
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Mapping {
+    pub range: Range<usize>,
+    pub device: Box<dyn Device>,
+}
+
+/// A `width` x `height` grid of cells stored row by row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<i64>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width * height],
+        }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> i64 {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn render<F>(&self, glyph: F) -> String
+    where
+        F: Fn(i64) -> char,
+    {
+        let mut out = String::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                out.push(glyph(self.pixel(x, y)));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl Device for Framebuffer {
+    fn read(&mut self, offset: usize) -> i64 {
+        self.pixels[offset]
+    }
+
+    fn write(&mut self, offset: usize, value: i64) {
+        self.pixels[offset] = value;
+    }
+
+    fn len(&self) -> usize {
+        self.pixels.len()
+    }
+}
+
+/// A single cell counting the instructions executed since it was last written.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Clock {
+    ticks: i64,
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ticks(&self) -> i64 {
+        self.ticks
+    }
+}
+
+impl Device for Clock {
+    fn read(&mut self, _offset: usize) -> i64 {
+        self.ticks
+    }
+
+    fn write(&mut self, _offset: usize, value: i64) {
+        self.ticks = value;
+    }
+
+    fn len(&self) -> usize {
+        1
+    }
+
+    fn tick(&mut self) {
+        self.ticks += 1;
+    }
+}
+
+/// A single cell for ASCII I/O. Reading takes the next character of input, or -1 once it
+/// runs out, and writing appends a character to the output.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Console {
+    input: VecDeque<char>,
+    output: String,
+}
+
+impl Console {
+    pub fn new(input: &str) -> Self {
+        Self {
+            input: input.chars().collect(),
+            output: String::new(),
+        }
+    }
+
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+}
+
+impl Device for Console {
+    fn read(&mut self, _offset: usize) -> i64 {
+        self.input.pop_front().map_or(-1, |c| c as i64)
+    }
+
+    fn write(&mut self, _offset: usize, value: i64) {
+        self.output.push(value as u8 as char);
+    }
+
+    fn len(&self) -> usize {
+        1
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::inconsistent_digit_grouping)]
+mod device_tests {
+    use super::*;
+    use crate::Computer;
+
+    #[test]
+    fn writes_to_framebuffer() {
+        let program: Vec<i64> = vec![
+            11_01, // 0: add
+            1,     // 1: 1
+            0,     // 2: 0
+            101,   // 3: ... to @101 (x = 1, y = 0)
+            11_01, // 4: add
+            2,     // 5: 2
+            0,     // 6: 0
+            104,   // 7: ... to @104 (x = 1, y = 1)
+            10_01, // 8: add
+            104,   // 9: @104
+            1,     // 10: 1
+            105,   // 11: ... to @105 (x = 2, y = 1)
+            99,    // 12: halt
+        ];
+        let mut computer = Computer::load(program);
+        computer.attach_device(100..106, Framebuffer::new(3, 2));
+        assert!(computer.run(vec![]).is_halted());
+        let screen: &Framebuffer = computer.device(100).unwrap();
+        assert_eq!(screen.pixel(2, 1), 3);
+        assert_eq!(screen.render(|v| b".#@+"[v as usize] as char), ".#.\n.@+\n");
+    }
+
+    #[test]
+    fn echoes_console_input_in_uppercase() {
+        let mut program: Vec<i64> = vec![
+            10_01, // 0: add
+            50,    // 1: @50 (console)
+            0,     // 2: 0
+            52,    // 3: ... to @52
+            10_07, // 4: less-than
+            52,    // 5: @52
+            0,     // 6: 0
+            51,    // 7: ... to @51
+            10_05, // 8: jump if true
+            51,    // 9: @51
+            29,    // 10: to @29
+            10_07, // 11: less-than
+            52,    // 12: @52
+            97,    // 13: 'a'
+            51,    // 14: ... to @51
+            10_05, // 15: jump if true
+            51,    // 16: @51
+            22,    // 17: to @22
+            10_01, // 18: add
+            52,    // 19: @52
+            -32,   // 20: -32
+            52,    // 21: ... to @52
+            10_01, // 22: add
+            52,    // 23: @52
+            0,     // 24: 0
+            50,    // 25: ... to @50 (console)
+            11_05, // 26: jump if true
+            1,     // 27: true
+            0,     // 28: to @0
+            99,    // 29: halt
+        ];
+        program.resize(53, 0);
+        let mut computer = Computer::load(program);
+        computer.attach_device(50..51, Console::new("take Sand!"));
+        let result = computer.run(vec![]);
+        assert_eq!(result.output, vec![]);
+        assert!(result.is_halted());
+        let console: &Console = computer.device(50).unwrap();
+        assert_eq!(console.output(), "TAKE SAND!");
+    }
+
+    #[test]
+    fn counts_instructions_on_clock() {
+        let program: Vec<i64> = vec![
+            11_01, // 0: add
+            0,     // 1: 0
+            0,     // 2: 0
+            20,    // 3: ... to @20 (reset clock)
+            11_01, // 4: add
+            0,     // 5: 0
+            0,     // 6: 0
+            30,    // 7: ... to @30
+            4,     // 8: output
+            20,    // 9: @20 (clock)
+            99,    // 10: halt
+        ];
+        let mut computer = Computer::load(program);
+        computer.attach_device(20..21, Clock::new());
+        assert_eq!(computer.run(vec![]).output, vec![2]);
+        let clock: &Clock = computer.device(20).unwrap();
+        assert_eq!(clock.ticks(), 4);
+    }
+
+    #[test]
+    fn clones_devices_with_computer() {
+        let program: Vec<i64> = vec![
+            3,  // 0: input
+            10, // 1: ... to @10 (console)
+            99, // 2: halt
+        ];
+        let mut computer = Computer::load(program);
+        computer.attach_device(10..11, Console::new(""));
+        let mut clone = computer.clone();
+        clone.run(vec![65]);
+        let console: &Console = clone.device(10).unwrap();
+        assert_eq!(console.output(), "A");
+        let console: &Console = computer.device(10).unwrap();
+        assert_eq!(console.output(), "");
+    }
+
+    #[test]
+    #[should_panic(expected = "device ranges overlap: 5..10 and 8..12")]
+    fn refuses_overlapping_devices() {
+        let mut computer = Computer::load(vec![99]);
+        computer.attach_device(5..10, Framebuffer::new(5, 1));
+        computer.attach_device(8..12, Framebuffer::new(4, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "device range 100..110 is larger than its 6 cells")]
+    fn refuses_ranges_larger_than_devices() {
+        let mut computer = Computer::load(vec![99]);
+        computer.attach_device(100..110, Framebuffer::new(3, 2));
+    }
+}
@@ -0,0 +1,275 @@
+use crate::Computer;
+use std::sync::Arc;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParamKind {
+    Read,
+    Dest,
+}
+
+/// Called with the resolved parameters of a custom instruction: values for `Read` params
+/// and addresses for `Dest` params. Returning false leaves the instruction unexecuted and
+/// pauses the computer until more input arrives.
+pub type Handler = Arc<dyn Fn(&mut Machine, &[i64]) -> bool + Send + Sync>;
+
+#[derive(Clone)]
+pub struct Extension {
+    pub params: Vec<ParamKind>,
+    pub handler: Handler,
+}
+
+/// The view of a `Computer` given to custom instruction handlers.
+pub struct Machine<'a> {
+    computer: &'a mut Computer,
+    pub(crate) bad_write: Option<i64>,
+}
+
+impl<'a> Machine<'a> {
+    pub(crate) fn new(computer: &'a mut Computer) -> Self {
+        Self {
+            computer,
+            bad_write: None,
+        }
+    }
+
+    pub fn read(&mut self, addr: usize) -> i64 {
+        self.computer.read_data(addr)
+    }
+
+    /// Writes `value` to `addr`. Writing outside of memory faults the instruction once the
+    /// handler returns.
+    pub fn write(&mut self, addr: usize, value: i64) {
+        if self.bad_write.is_some() {
+            return;
+        }
+        if !self.computer.writable(addr as i64) {
+            self.bad_write = Some(addr as i64);
+            return;
+        }
+        self.computer.write(addr, value);
+    }
+
+    pub fn has_input(&self) -> bool {
+        !self.computer.input.is_empty()
+    }
+
+    pub fn input(&mut self) -> Option<i64> {
+        self.computer.input.pop_front()
+    }
+
+    pub fn output(&mut self, value: i64) {
+        self.computer.output.push(value);
+    }
+
+    pub fn jump(&mut self, addr: usize) {
+        self.computer.pos = addr;
+    }
+
+    pub fn relative_base(&self) -> i64 {
+        self.computer.relative_base
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::inconsistent_digit_grouping)]
+mod extension_tests {
+    use super::*;
+    use crate::{Fault, StopReason};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn runs_registered_opcodes() {
+        let program: Vec<i64> = vec![
+            1_42, // 0: custom "square" (immediate, position)
+            7,    // 1: 7
+            8,    // 2: ... to @8
+            4,    // 3: output
+            8,    // 4: @8
+            99,   // 5: halt
+            0,    // 6
+            0,    // 7
+            0,    // 8
+        ];
+        let mut computer = Computer::load(program);
+        computer.register_opcode(
+            42,
+            vec![ParamKind::Read, ParamKind::Dest],
+            Arc::new(|machine, params| {
+                machine.write(params[1] as usize, params[0] * params[0]);
+                true
+            }),
+        );
+        assert_eq!(computer.run(vec![]).output, vec![49]);
+    }
+
+    #[test]
+    fn gives_handlers_access_to_io() {
+        let program: Vec<i64> = vec![
+            50, // 0: custom "echo twice"
+            50, // 1: custom "echo twice"
+            99, // 2: halt
+        ];
+        let mut computer = Computer::load(program);
+        computer.register_opcode(
+            50,
+            vec![],
+            Arc::new(|machine, _| match machine.input() {
+                Some(v) => {
+                    machine.output(v);
+                    machine.output(v);
+                    true
+                }
+                None => false,
+            }),
+        );
+        let result = computer.run(vec![3]);
+        assert_eq!(result.output, vec![3, 3]);
+        assert_eq!(result.stop, StopReason::NeedsInput);
+        let result = computer.run(vec![4]);
+        assert_eq!(result.output, vec![4, 4]);
+        assert!(result.is_halted());
+    }
+
+    #[test]
+    fn resolves_relative_parameters() {
+        let program: Vec<i64> = vec![
+            109,   // 0: adjust relative base
+            10,    // 1: ... to 10
+            22_20, // 2: custom "copy" (relative, relative)
+            1,     // 3: @rb+1 = @11
+            2,     // 4: ... to @rb+2 = @12
+            4,     // 5: output
+            12,    // 6: @12
+            99,    // 7: halt
+            0,     // 8
+            0,     // 9
+            0,     // 10
+            123,   // 11
+            0,     // 12
+        ];
+        let mut computer = Computer::load(program);
+        computer.register_opcode(
+            20,
+            vec![ParamKind::Read, ParamKind::Dest],
+            Arc::new(|machine, params| {
+                machine.write(params[1] as usize, params[0]);
+                true
+            }),
+        );
+        assert_eq!(computer.run(vec![]).output, vec![123]);
+    }
+
+    #[test]
+    fn handlers_can_jump() {
+        let program: Vec<i64> = vec![
+            1_30, // 0: custom "goto"
+            4,    // 1: 4
+            1_04, // 2: output (skipped)
+            1,    // 3: 1
+            1_04, // 4: output
+            2,    // 5: 2
+            99,   // 6: halt
+        ];
+        let mut computer = Computer::load(program);
+        computer.register_opcode(
+            30,
+            vec![ParamKind::Read],
+            Arc::new(|machine, params| {
+                machine.jump(params[0] as usize);
+                true
+            }),
+        );
+        assert_eq!(computer.run(vec![]).output, vec![2]);
+    }
+
+    #[test]
+    fn cloned_computers_share_handlers() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let mut computer = Computer::load(vec![60, 99]);
+        computer.register_opcode(
+            60,
+            vec![],
+            Arc::new(move |_, _| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                true
+            }),
+        );
+        computer.clone().run(vec![]);
+        computer.run(vec![]);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn faults_on_malformed_custom_instructions() {
+        let fault = |program: Vec<i64>| {
+            let mut computer = Computer::load(program);
+            computer.register_opcode(
+                42,
+                vec![ParamKind::Read, ParamKind::Dest],
+                Arc::new(|machine, params| {
+                    machine.write(params[1] as usize, params[0]);
+                    true
+                }),
+            );
+            match computer.run(vec![]).stop {
+                StopReason::Fault(fault) => fault,
+                stop => panic!("expected a fault, got {:?}", stop),
+            }
+        };
+        assert_eq!(
+            fault(vec![3_42, 0, 0, 99]),
+            Fault::InvalidMode {
+                addr: 0,
+                value: 3_42
+            }
+        );
+        assert_eq!(
+            fault(vec![11_42, 0, 0, 99]),
+            Fault::ImmediateDestination { addr: 0 }
+        );
+        assert_eq!(
+            fault(vec![1_42, 0, 20_000, 99]),
+            Fault::AddressOutOfRange {
+                addr: 0,
+                address: 20_000
+            }
+        );
+    }
+
+    #[test]
+    fn faults_on_writes_outside_memory() {
+        let mut computer = Computer::load(vec![42, 99]);
+        computer.register_opcode(
+            42,
+            vec![],
+            Arc::new(|machine, _| {
+                machine.write(20_000, 1);
+                machine.write(1, 0);
+                true
+            }),
+        );
+        assert_eq!(
+            computer.run(vec![]).stop,
+            StopReason::Fault(Fault::AddressOutOfRange {
+                addr: 0,
+                address: 20_000
+            })
+        );
+        assert_eq!(computer.memory()[..2], [42, 99]);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot override standard opcode: 1")]
+    fn refuses_to_override_standard_opcodes() {
+        let mut computer = Computer::load(vec![99]);
+        computer.register_opcode(1, vec![], Arc::new(|_, _| true));
+    }
+
+    #[test]
+    #[should_panic(expected = "custom opcode out of range 1..=98: 142")]
+    fn refuses_opcodes_that_can_never_run() {
+        let mut computer = Computer::load(vec![99]);
+        computer.register_opcode(142, vec![], Arc::new(|_, _| true));
+    }
+}
@@ -1,6 +1,28 @@
+use coverage::Coverage;
+use device::{Device, Mapping};
+use extension::{Extension, Handler, Machine, ParamKind};
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
-use std::collections::VecDeque;
+use outputs::Outputs;
+use self_modification::SelfModificationTracker;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fmt;
+use std::ops::Range;
+
+pub mod batch;
+pub mod corpus;
+pub mod coverage;
+pub mod decompile;
+pub mod device;
+pub mod extension;
+pub mod lint;
+pub mod optimize;
+pub mod outputs;
+pub mod scheduler;
+pub mod self_modification;
+pub mod session;
+pub mod symbolic;
+pub mod transpile;
 
 const MEMORY: usize = 10_000;
 
@@ -18,6 +40,41 @@ enum Opcode {
     AdjustRelativeBase = 9,
 }
 
+impl Opcode {
+    fn mnemonic(&self) -> &'static str {
+        match self {
+            Opcode::Halt => "hlt",
+            Opcode::Add => "add",
+            Opcode::Multiply => "mul",
+            Opcode::Input => "in",
+            Opcode::Output => "out",
+            Opcode::JumpIfTrue => "jt",
+            Opcode::JumpIfFalse => "jf",
+            Opcode::LessThan => "lt",
+            Opcode::Equals => "eq",
+            Opcode::AdjustRelativeBase => "arb",
+        }
+    }
+
+    // The index of the parameter an instruction writes to.
+    fn dest_param(&self) -> Option<usize> {
+        match self {
+            Opcode::Add | Opcode::Multiply | Opcode::LessThan | Opcode::Equals => Some(2),
+            Opcode::Input => Some(0),
+            _ => None,
+        }
+    }
+
+    fn num_params(&self) -> usize {
+        match self {
+            Opcode::Halt => 0,
+            Opcode::Input | Opcode::Output | Opcode::AdjustRelativeBase => 1,
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse => 2,
+            Opcode::Add | Opcode::Multiply | Opcode::LessThan | Opcode::Equals => 3,
+        }
+    }
+}
+
 #[derive(FromPrimitive)]
 enum Mode {
     Position = 0,
@@ -25,6 +82,54 @@ enum Mode {
     Relative = 2,
 }
 
+impl Mode {
+    fn format_param(&self, v: i64) -> String {
+        match self {
+            Mode::Position => format!("@{}", v),
+            Mode::Immediate => format!("{}", v),
+            Mode::Relative if v < 0 => format!("@rb{}", v),
+            Mode::Relative => format!("@rb+{}", v),
+        }
+    }
+}
+
+/// Decodes the instruction at `addr` into its opcode and (mode, raw value) parameters.
+/// Returns `None` if the opcode or any of the mode digits are invalid.
+fn decode(program: &[i64], addr: usize) -> Option<(Opcode, Vec<(Mode, i64)>)> {
+    let val = *program.get(addr)?;
+    let opcode: Opcode = FromPrimitive::from_i64(val % 100)?;
+    let params = decode_modes(val, opcode.num_params())?
+        .into_iter()
+        .enumerate()
+        .map(|(i, mode)| (mode, *program.get(addr + 1 + i).unwrap_or(&0)))
+        .collect();
+    Some((opcode, params))
+}
+
+// The modes of an instruction's parameters, or `None` if any digit isn't a valid mode.
+fn decode_modes(val: i64, num_params: usize) -> Option<Vec<Mode>> {
+    let mut modes = val / 100;
+    let mut decoded = vec![];
+    for _ in 0..num_params {
+        decoded.push(FromPrimitive::from_i64(modes % 10)?);
+        modes /= 10;
+    }
+    if modes != 0 {
+        return None;
+    }
+    Some(decoded)
+}
+
+fn disassemble(program: &[i64], addr: usize) -> Option<(String, usize)> {
+    let (opcode, params) = decode(program, addr)?;
+    let mut text = opcode.mnemonic().to_owned();
+    for (i, (mode, v)) in params.iter().enumerate() {
+        text.push_str(if i == 0 { " " } else { ", " });
+        text.push_str(&mode.format_param(*v));
+    }
+    Some((text, params.len() + 1))
+}
+
 struct ModeGenerator {
     val: i64,
 }
@@ -37,6 +142,59 @@ impl ModeGenerator {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Fault {
+    InvalidOpcode { addr: usize, value: i64 },
+    InvalidMode { addr: usize, value: i64 },
+    ImmediateDestination { addr: usize },
+    AddressOutOfRange { addr: usize, address: i64 },
+    AlreadyHalted,
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Fault::InvalidOpcode { addr, value } => {
+                write!(f, "invalid opcode {} at address {}", value, addr)
+            }
+            Fault::InvalidMode { addr, value } => {
+                write!(f, "invalid parameter mode in {} at address {}", value, addr)
+            }
+            Fault::ImmediateDestination { addr } => {
+                write!(f, "immediate mode destination at address {}", addr)
+            }
+            Fault::AddressOutOfRange { addr, address } => write!(
+                f,
+                "instruction at address {} accesses out of range address {}",
+                addr, address
+            ),
+            Fault::AlreadyHalted => write!(f, "computer has already halted"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    Halted,
+    NeedsInput,
+    Breakpoint(usize),
+    BudgetExhausted,
+    Fault(Fault),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RunResult {
+    pub output: Vec<i64>,
+    pub instructions: u64,
+    pub stop: StopReason,
+}
+
+impl RunResult {
+    pub fn is_halted(&self) -> bool {
+        self.stop == StopReason::Halted
+    }
+}
+
 #[derive(Clone)]
 pub struct Computer {
     program: Vec<i64>,
@@ -44,6 +202,16 @@ pub struct Computer {
     output: Vec<i64>,
     pos: usize,
     relative_base: i64,
+    coverage: Option<Coverage>,
+    self_modification: Option<SelfModificationTracker>,
+    extensions: HashMap<i64, Extension>,
+    devices: Vec<Mapping>,
+    halted: bool,
+    breakpoints: BTreeSet<usize>,
+    // The breakpoint the computer last stopped at, which is skipped when it resumes.
+    resume_from: Option<usize>,
+    budget: Option<u64>,
+    instructions: u64,
 }
 
 impl Computer {
@@ -57,6 +225,15 @@ impl Computer {
             pos: 0,
             output: Vec::new(),
             relative_base: 0,
+            coverage: None,
+            self_modification: None,
+            extensions: HashMap::new(),
+            devices: vec![],
+            halted: false,
+            breakpoints: BTreeSet::new(),
+            resume_from: None,
+            budget: None,
+            instructions: 0,
         }
     }
 
@@ -70,20 +247,88 @@ impl Computer {
         Self::load(program)
     }
 
-    pub fn run(&mut self, input: Vec<i64>) -> (Vec<i64>, bool) {
+    pub fn run(&mut self, input: Vec<i64>) -> RunResult {
+        self.input = input.into();
+        self.output = Vec::new();
+        self.instructions = 0;
+        let stop = if self.halted {
+            StopReason::Fault(Fault::AlreadyHalted)
+        } else {
+            let stop = self.execute(false).unwrap();
+            self.halted = stop == StopReason::Halted;
+            stop
+        };
+        RunResult {
+            output: self.output.clone(),
+            instructions: self.instructions,
+            stop,
+        }
+    }
+
+    /// Returns an iterator that runs the computer only as far as is needed to produce each
+    /// output value.
+    pub fn outputs(&mut self, input: Vec<i64>) -> Outputs<'_> {
         self.input = input.into();
         self.output = Vec::new();
+        self.instructions = 0;
+        Outputs::new(self)
+    }
 
-        let completed = loop {
+    // Runs until the computer stops, or returns `None` as soon as there is output if
+    // `until_output` is set.
+    fn execute(&mut self, until_output: bool) -> Option<StopReason> {
+        Some(loop {
+            if until_output && !self.output.is_empty() {
+                return None;
+            }
             let start_pos = self.pos;
-            match self.read_opcode() {
+            if self.breakpoints.contains(&start_pos) && self.resume_from != Some(start_pos) {
+                self.resume_from = Some(start_pos);
+                break StopReason::Breakpoint(start_pos);
+            }
+            if self
+                .budget
+                .is_some_and(|budget| self.instructions >= budget)
+            {
+                break StopReason::BudgetExhausted;
+            }
+            if let Some(extension) = self.extension_at(start_pos) {
+                let kinds = &extension.params;
+                if let Some(fault) =
+                    self.param_fault(start_pos, kinds.len(), |i| kinds[i] == ParamKind::Dest)
+                {
+                    break StopReason::Fault(fault);
+                }
+                match self.run_extension(extension) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        self.pos = start_pos;
+                        break StopReason::NeedsInput;
+                    }
+                    Err(fault) => {
+                        self.pos = start_pos;
+                        break StopReason::Fault(fault);
+                    }
+                }
+                self.record_instruction(start_pos);
+                continue;
+            }
+            if let Some(fault) = self.fault_at(start_pos) {
+                break StopReason::Fault(fault);
+            }
+            let (opcode, mg) = self.read_opcode();
+            if let Some(tracker) = &mut self.self_modification {
+                tracker.record_instruction(start_pos, opcode.num_params() + 1);
+            }
+            match (opcode, mg) {
                 (Opcode::Halt, _) => {
-                    break true;
+                    self.record_instruction(start_pos);
+                    break StopReason::Halted;
                 }
                 (Opcode::Input, mg) => {
                     if !self.op_input(mg) {
                         self.pos = start_pos;
-                        break false;
+                        break StopReason::NeedsInput;
                     }
                 }
                 (Opcode::Output, mg) => self.op_output(mg),
@@ -95,14 +340,229 @@ impl Computer {
                 (Opcode::LessThan, mg) => self.op_less_than(mg),
                 (Opcode::Equals, mg) => self.op_equals(mg),
             }
+            self.record_instruction(start_pos);
+        })
+    }
+
+    // Checks the instruction at `addr` can be executed without corrupting the computer.
+    fn fault_at(&self, addr: usize) -> Option<Fault> {
+        let value = self.value_at(addr);
+        let opcode = match Opcode::from_i64(value % 100) {
+            Some(opcode) => opcode,
+            None => return Some(Fault::InvalidOpcode { addr, value }),
         };
-        (self.output.clone(), completed)
+        let dest = opcode.dest_param();
+        self.param_fault(addr, opcode.num_params(), |i| dest == Some(i))
+    }
+
+    // Checks the modes of the instruction at `addr` and the addresses its parameters access.
+    fn param_fault(
+        &self,
+        addr: usize,
+        num_params: usize,
+        is_dest: impl Fn(usize) -> bool,
+    ) -> Option<Fault> {
+        let value = self.value_at(addr);
+        let mut modes = value / 100;
+        for i in 0..num_params {
+            let raw = self.value_at(addr + 1 + i);
+            let mode = Mode::from_i64(modes % 10);
+            modes /= 10;
+            let address = match mode {
+                Some(Mode::Immediate) if is_dest(i) => {
+                    return Some(Fault::ImmediateDestination { addr })
+                }
+                Some(Mode::Immediate) => continue,
+                Some(Mode::Position) => raw,
+                Some(Mode::Relative) => self.relative_base + raw,
+                None => return Some(Fault::InvalidMode { addr, value }),
+            };
+            if address < 0 || (is_dest(i) && !self.writable(address)) {
+                return Some(Fault::AddressOutOfRange { addr, address });
+            }
+        }
+        if modes != 0 {
+            return Some(Fault::InvalidMode { addr, value });
+        }
+        None
+    }
+
+    fn writable(&self, address: i64) -> bool {
+        address >= 0
+            && ((address as usize) < self.program.len()
+                || self
+                    .devices
+                    .iter()
+                    .any(|m| m.range.contains(&(address as usize))))
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    pub fn set_breakpoint(&mut self, addr: usize) {
+        self.breakpoints.insert(addr);
+    }
+
+    pub fn clear_breakpoint(&mut self, addr: usize) {
+        self.breakpoints.remove(&addr);
+    }
+
+    /// Limits how many instructions each call to `run` may execute.
+    pub fn set_instruction_budget(&mut self, budget: Option<u64>) {
+        self.budget = budget;
     }
 
     pub fn set_address(&mut self, address: usize, value: i64) {
         self.program[address] = value;
     }
 
+    pub fn memory(&self) -> &[i64] {
+        &self.program
+    }
+
+    pub fn enable_coverage(&mut self) {
+        if self.coverage.is_none() {
+            self.coverage = Some(Coverage::new());
+        }
+    }
+
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    pub fn enable_self_modification_tracking(&mut self) {
+        if self.self_modification.is_none() {
+            self.self_modification = Some(SelfModificationTracker::new());
+        }
+    }
+
+    pub fn self_modifications(&self) -> Option<&SelfModificationTracker> {
+        self.self_modification.as_ref()
+    }
+
+    /// Maps `device` over the addresses in `range`, which must not overlap another device or
+    /// extend past the cells the device provides.
+    pub fn attach_device<D: Device + 'static>(&mut self, range: Range<usize>, device: D) {
+        if range.len() > device.len() {
+            panic!(
+                "device range {:?} is larger than its {} cells",
+                range,
+                device.len()
+            );
+        }
+        if let Some(other) = self
+            .devices
+            .iter()
+            .find(|m| m.range.start < range.end && range.start < m.range.end)
+        {
+            panic!("device ranges overlap: {:?} and {:?}", other.range, range);
+        }
+        self.devices.push(Mapping {
+            range,
+            device: Box::new(device),
+        });
+    }
+
+    /// Returns the device of type `D` mapped over `addr`.
+    pub fn device<D: Device + 'static>(&self, addr: usize) -> Option<&D> {
+        self.devices
+            .iter()
+            .find(|m| m.range.contains(&addr))
+            .and_then(|m| m.device.as_any().downcast_ref())
+    }
+
+    /// Adds a custom instruction with an opcode from 1 to 98. Standard opcodes cannot be
+    /// overridden.
+    pub fn register_opcode(&mut self, opcode: i64, params: Vec<ParamKind>, handler: Handler) {
+        let standard: Option<Opcode> = FromPrimitive::from_i64(opcode);
+        if standard.is_some() {
+            panic!("cannot override standard opcode: {}", opcode);
+        }
+        if !(1..=98).contains(&opcode) {
+            panic!("custom opcode out of range 1..=98: {}", opcode);
+        }
+        self.extensions
+            .insert(opcode, Extension { params, handler });
+    }
+
+    fn extension_at(&self, addr: usize) -> Option<Extension> {
+        if self.extensions.is_empty() {
+            return None;
+        }
+        let opcode = self.value_at(addr) % 100;
+        let standard: Option<Opcode> = FromPrimitive::from_i64(opcode);
+        if standard.is_some() {
+            return None;
+        }
+        self.extensions.get(&opcode).cloned()
+    }
+
+    // Runs the handler, failing if it wrote outside of memory. Writes after the first bad one
+    // are dropped.
+    fn run_extension(&mut self, extension: Extension) -> Result<bool, Fault> {
+        let start_pos = self.pos;
+        let mut mode_gen = ModeGenerator {
+            val: self.read() / 100,
+        };
+        if let Some(tracker) = &mut self.self_modification {
+            tracker.record_instruction(start_pos, extension.params.len() + 1);
+        }
+        let params: Vec<i64> = extension
+            .params
+            .iter()
+            .map(|kind| match kind {
+                ParamKind::Read => self.read_param(&mut mode_gen),
+                ParamKind::Dest => self.read_dest_addr(&mut mode_gen) as i64,
+            })
+            .collect();
+        let mut machine = Machine::new(self);
+        let resumed = (extension.handler)(&mut machine, &params);
+        match machine.bad_write {
+            Some(address) => Err(Fault::AddressOutOfRange {
+                addr: start_pos,
+                address,
+            }),
+            None => Ok(resumed),
+        }
+    }
+
+    fn record_instruction(&mut self, addr: usize) {
+        self.instructions += 1;
+        self.resume_from = None;
+        if let Some(coverage) = &mut self.coverage {
+            coverage.record_instruction(addr);
+        }
+        for mapping in &mut self.devices {
+            mapping.device.tick();
+        }
+    }
+
+    fn device_at(&mut self, addr: usize) -> Option<(&mut Box<dyn Device>, usize)> {
+        self.devices
+            .iter_mut()
+            .find(|m| m.range.contains(&addr))
+            .map(|m| (&mut m.device, addr - m.range.start))
+    }
+
+    fn read_data(&mut self, addr: usize) -> i64 {
+        match self.device_at(addr) {
+            Some((device, offset)) => device.read(offset),
+            None => self.value_at(addr),
+        }
+    }
+
+    fn write(&mut self, addr: usize, value: i64) {
+        if let Some((device, offset)) = self.device_at(addr) {
+            device.write(offset, value);
+            return;
+        }
+        if let Some(tracker) = &mut self.self_modification {
+            tracker.record_write(addr, self.program[addr], value);
+        }
+        self.program[addr] = value;
+    }
+
     fn read(&mut self) -> i64 {
         let val = self.value_at(self.pos);
         self.pos += 1;
@@ -125,9 +585,9 @@ impl Computer {
     fn read_param(&mut self, mode_gen: &mut ModeGenerator) -> i64 {
         let v = self.read();
         match mode_gen.next() {
-            Mode::Position => self.value_at(v as usize),
+            Mode::Position => self.read_data(v as usize),
             Mode::Immediate => v,
-            Mode::Relative => self.value_at((self.relative_base + v) as usize),
+            Mode::Relative => self.read_data((self.relative_base + v) as usize),
         }
     }
 
@@ -145,7 +605,8 @@ impl Computer {
             return false;
         }
         let dest_addr = self.read_dest_addr(&mut mode_gen);
-        self.program[dest_addr] = self.input.pop_front().unwrap();
+        let value = self.input.pop_front().unwrap();
+        self.write(dest_addr, value);
         true
     }
 
@@ -172,7 +633,11 @@ impl Computer {
     {
         let test_val = self.read_param(&mut mode_gen);
         let dest = self.read_param(&mut mode_gen) as usize;
-        if test(test_val) {
+        let taken = test(test_val);
+        if let Some(coverage) = &mut self.coverage {
+            coverage.record_branch(self.pos - 3, taken);
+        }
+        if taken {
             self.pos = dest;
         }
     }
@@ -192,7 +657,7 @@ impl Computer {
         let v1 = self.read_param(&mut mode_gen);
         let v2 = self.read_param(&mut mode_gen);
         let dest_addr = self.read_dest_addr(&mut mode_gen);
-        self.program[dest_addr] = if test(v1, v2) { 1 } else { 0 };
+        self.write(dest_addr, if test(v1, v2) { 1 } else { 0 });
     }
 
     fn op_add(&mut self, mut mode_gen: ModeGenerator) {
@@ -210,11 +675,12 @@ impl Computer {
         let param1 = self.read_param(mode_gen);
         let param2 = self.read_param(mode_gen);
         let dest_addr = self.read_dest_addr(mode_gen);
-        self.program[dest_addr] = op(param1, param2);
+        self.write(dest_addr, op(param1, param2));
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::inconsistent_digit_grouping)]
 mod computer_tests {
     use super::*;
     use std::env::temp_dir;
@@ -228,9 +694,9 @@ mod computer_tests {
         let mut file = File::create(&path).unwrap();
         file.write_all(b"104,123,99\n").unwrap();
         let path = path.to_str().unwrap();
-        let (out, complete) = Computer::load_from_file(path).run(vec![]);
-        assert_eq!(out, vec![123]);
-        assert_eq!(complete, true);
+        let result = Computer::load_from_file(path).run(vec![]);
+        assert_eq!(result.output, vec![123]);
+        assert!(result.is_halted());
     }
 
     #[test]
@@ -246,7 +712,7 @@ mod computer_tests {
             2,  // 7
             3,  // 8
         ];
-        let (out, _) = Computer::load(program).run(vec![]);
+        let out = Computer::load(program).run(vec![]).output;
         assert_eq!(out, vec![5]);
     }
 
@@ -263,7 +729,7 @@ mod computer_tests {
             2,  // 7
             3,  // 8
         ];
-        let (out, _) = Computer::load(program).run(vec![]);
+        let out = Computer::load(program).run(vec![]).output;
         assert_eq!(out, vec![6]);
     }
 
@@ -276,7 +742,7 @@ mod computer_tests {
             0,  // 3: addr 0
             99, // 4: halt
         ];
-        let (out, _) = Computer::load(program).run(vec![101]);
+        let out = Computer::load(program).run(vec![101]).output;
         assert_eq!(out, vec![101]);
 
         let program: Vec<i64> = vec![
@@ -288,7 +754,7 @@ mod computer_tests {
             1,  // 5: addr 1
             99, // 6: halt
         ];
-        let (out, _) = Computer::load(program).run(vec![101, 102]);
+        let out = Computer::load(program).run(vec![101, 102]).output;
         assert_eq!(out, vec![102]);
     }
 
@@ -301,7 +767,7 @@ mod computer_tests {
             4,  // 3: addr 4 = 99
             99, // 4: halt
         ];
-        let (out, _) = Computer::load(program).run(vec![101]);
+        let out = Computer::load(program).run(vec![101]).output;
         assert_eq!(out, vec![4, 99]);
     }
 
@@ -317,7 +783,7 @@ mod computer_tests {
             99,  // 6: halt
             30,  // 7
         ];
-        let (out, _) = Computer::load(program).run(vec![]);
+        let out = Computer::load(program).run(vec![]).output;
         assert_eq!(out, vec![50]);
     }
 
@@ -333,7 +799,7 @@ mod computer_tests {
             99,   // 6: halt
             20,   // 7
         ];
-        let (out, _) = Computer::load(program).run(vec![]);
+        let out = Computer::load(program).run(vec![]).output;
         assert_eq!(out, vec![50]);
     }
 
@@ -349,7 +815,7 @@ mod computer_tests {
             102,   // 6: value 102
             99,    // 7: halt
         ];
-        let (out, _) = Computer::load(program).run(vec![]);
+        let out = Computer::load(program).run(vec![]).output;
         assert_eq!(out, vec![101, 102]);
 
         let program: Vec<i64> = vec![
@@ -362,7 +828,7 @@ mod computer_tests {
             102,   // 6: value 102
             99,    // 7: halt
         ];
-        let (out, _) = Computer::load(program).run(vec![]);
+        let out = Computer::load(program).run(vec![]).output;
         assert_eq!(out, vec![102]);
     }
 
@@ -378,7 +844,7 @@ mod computer_tests {
             102,   // 6: value 102
             99,    // 7: halt
         ];
-        let (out, _) = Computer::load(program).run(vec![]);
+        let out = Computer::load(program).run(vec![]).output;
         assert_eq!(out, vec![102]);
 
         let program: Vec<i64> = vec![
@@ -391,7 +857,7 @@ mod computer_tests {
             102,   // 6: value 102
             99,    // 7: halt
         ];
-        let (out, _) = Computer::load(program).run(vec![]);
+        let out = Computer::load(program).run(vec![]).output;
         assert_eq!(out, vec![101, 102]);
     }
 
@@ -406,7 +872,7 @@ mod computer_tests {
             0,     // 5: addr 0 (value = 1)
             99,    // 6: halt
         ];
-        let (out, _) = Computer::load(program).run(vec![]);
+        let out = Computer::load(program).run(vec![]).output;
         assert_eq!(out, vec![1]);
 
         let program: Vec<i64> = vec![
@@ -418,7 +884,7 @@ mod computer_tests {
             0,     // 5: addr 0 (value = 0)
             99,    // 6: halt
         ];
-        let (out, _) = Computer::load(program).run(vec![]);
+        let out = Computer::load(program).run(vec![]).output;
         assert_eq!(out, vec![0]);
 
         let program: Vec<i64> = vec![
@@ -430,7 +896,7 @@ mod computer_tests {
             0,     // 5: addr 0 (value = 0)
             99,    // 6: halt
         ];
-        let (out, _) = Computer::load(program).run(vec![]);
+        let out = Computer::load(program).run(vec![]).output;
         assert_eq!(out, vec![0]);
     }
 
@@ -445,7 +911,7 @@ mod computer_tests {
             0,     // 5: addr 0 (value = 1)
             99,    // 6: halt
         ];
-        let (out, _) = Computer::load(program).run(vec![]);
+        let out = Computer::load(program).run(vec![]).output;
         assert_eq!(out, vec![1]);
 
         let program: Vec<i64> = vec![
@@ -457,7 +923,7 @@ mod computer_tests {
             0,     // 5: addr 0 (value = 0)
             99,    // 6: halt
         ];
-        let (out, _) = Computer::load(program).run(vec![]);
+        let out = Computer::load(program).run(vec![]).output;
         assert_eq!(out, vec![0]);
     }
 
@@ -476,13 +942,13 @@ mod computer_tests {
         ];
 
         let mut computer = Computer::load(program);
-        let (out, complete) = computer.run(vec![101]);
-        assert_eq!(out, vec![99]);
-        assert_eq!(complete, false);
+        let result = computer.run(vec![101]);
+        assert_eq!(result.output, vec![99]);
+        assert_eq!(result.stop, StopReason::NeedsInput);
 
-        let (out, complete) = computer.run(vec![102]);
-        assert_eq!(out, vec![102]);
-        assert_eq!(complete, true);
+        let result = computer.run(vec![102]);
+        assert_eq!(result.output, vec![102]);
+        assert_eq!(result.stop, StopReason::Halted);
     }
 
     #[test]
@@ -493,7 +959,7 @@ mod computer_tests {
             99,  // 2: halt
             101, // 3
         ];
-        assert_eq!(Computer::load(program).run(vec![]).0, vec![101]);
+        assert_eq!(Computer::load(program).run(vec![]).output, vec![101]);
 
         let program: Vec<i64> = vec![
             109, // 0: adjust relative base
@@ -509,7 +975,7 @@ mod computer_tests {
             222, // 10
             333, // 11
         ];
-        assert_eq!(Computer::load(program).run(vec![]).0, vec![111, 222]);
+        assert_eq!(Computer::load(program).run(vec![]).output, vec![111, 222]);
     }
 
     #[test]
@@ -524,7 +990,7 @@ mod computer_tests {
             99,  // 6: halt
             0,   // 7
         ];
-        assert_eq!(Computer::load(program).run(vec![123]).0, vec![123]);
+        assert_eq!(Computer::load(program).run(vec![123]).output, vec![123]);
     }
 
     #[test]
@@ -541,7 +1007,7 @@ mod computer_tests {
             99,    // 8: halt
             0,     // 9
         ];
-        assert_eq!(Computer::load(program).run(vec![]).0, vec![5]);
+        assert_eq!(Computer::load(program).run(vec![]).output, vec![5]);
     }
 
     #[test]
@@ -558,7 +1024,7 @@ mod computer_tests {
             99,     // 8: halt
             0,      // 9
         ];
-        assert_eq!(Computer::load(program).run(vec![]).0, vec![1]);
+        assert_eq!(Computer::load(program).run(vec![]).output, vec![1]);
     }
 
     #[test]
@@ -568,14 +1034,14 @@ mod computer_tests {
             3,  // 1: ... from address 3
             99, // 2: halt
         ];
-        assert_eq!(Computer::load(program).run(vec![]).0, vec![0]);
+        assert_eq!(Computer::load(program).run(vec![]).output, vec![0]);
 
         let program: Vec<i64> = vec![
             204, // 0: output using position mode
             3,   // 1: ... from address 3
             99,  // 2: halt
         ];
-        assert_eq!(Computer::load(program).run(vec![]).0, vec![0]);
+        assert_eq!(Computer::load(program).run(vec![]).output, vec![0]);
     }
 
     #[test]
@@ -599,7 +1065,7 @@ mod computer_tests {
             11_05, // 7: jump if true (immediate mode)
             1,     // 8: true
         ];
-        assert_eq!(Computer::load(program).run(vec![0]).0, vec![]);
+        assert_eq!(Computer::load(program).run(vec![0]).output, vec![]);
     }
 
     #[test]
@@ -611,7 +1077,7 @@ mod computer_tests {
             5,  // 3: ... from @5
             99, // 4: halt
         ];
-        assert_eq!(Computer::load(program).run(vec![101]).0, vec![101]);
+        assert_eq!(Computer::load(program).run(vec![101]).output, vec![101]);
     }
 
     #[test]
@@ -622,7 +1088,7 @@ mod computer_tests {
             99,               // 2: halt
         ];
         assert_eq!(
-            Computer::load(program).run(vec![]).0,
+            Computer::load(program).run(vec![]).output,
             vec![1125899906842624]
         );
     }
@@ -630,49 +1096,70 @@ mod computer_tests {
     #[test]
     fn examples() {
         let program: Vec<i64> = vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
-        assert_eq!(Computer::load(program.clone()).run(vec![8]).0, vec![1]);
-        assert_eq!(Computer::load(program.clone()).run(vec![101]).0, vec![0]);
+        assert_eq!(Computer::load(program.clone()).run(vec![8]).output, vec![1]);
+        assert_eq!(
+            Computer::load(program.clone()).run(vec![101]).output,
+            vec![0]
+        );
 
         let program: Vec<i64> = vec![3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8];
-        assert_eq!(Computer::load(program.clone()).run(vec![7]).0, vec![1]);
-        assert_eq!(Computer::load(program.clone()).run(vec![8]).0, vec![0]);
+        assert_eq!(Computer::load(program.clone()).run(vec![7]).output, vec![1]);
+        assert_eq!(Computer::load(program.clone()).run(vec![8]).output, vec![0]);
 
         let program: Vec<i64> = vec![3, 3, 1108, -1, 8, 3, 4, 3, 99];
-        assert_eq!(Computer::load(program.clone()).run(vec![8]).0, vec![1]);
-        assert_eq!(Computer::load(program.clone()).run(vec![101]).0, vec![0]);
+        assert_eq!(Computer::load(program.clone()).run(vec![8]).output, vec![1]);
+        assert_eq!(
+            Computer::load(program.clone()).run(vec![101]).output,
+            vec![0]
+        );
 
         let program: Vec<i64> = vec![3, 3, 1107, -1, 8, 3, 4, 3, 99];
-        assert_eq!(Computer::load(program.clone()).run(vec![7]).0, vec![1]);
-        assert_eq!(Computer::load(program.clone()).run(vec![8]).0, vec![0]);
+        assert_eq!(Computer::load(program.clone()).run(vec![7]).output, vec![1]);
+        assert_eq!(Computer::load(program.clone()).run(vec![8]).output, vec![0]);
 
         let program: Vec<i64> = vec![3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
-        assert_eq!(Computer::load(program.clone()).run(vec![0]).0, vec![0]);
-        assert_eq!(Computer::load(program.clone()).run(vec![101]).0, vec![1]);
+        assert_eq!(Computer::load(program.clone()).run(vec![0]).output, vec![0]);
+        assert_eq!(
+            Computer::load(program.clone()).run(vec![101]).output,
+            vec![1]
+        );
 
         let program: Vec<i64> = vec![3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
-        assert_eq!(Computer::load(program.clone()).run(vec![0]).0, vec![0]);
-        assert_eq!(Computer::load(program.clone()).run(vec![101]).0, vec![1]);
+        assert_eq!(Computer::load(program.clone()).run(vec![0]).output, vec![0]);
+        assert_eq!(
+            Computer::load(program.clone()).run(vec![101]).output,
+            vec![1]
+        );
 
         let program: Vec<i64> = vec![
             3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
             0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
             20, 1105, 1, 46, 98, 99,
         ];
-        assert_eq!(Computer::load(program.clone()).run(vec![7]).0, vec![999]);
-        assert_eq!(Computer::load(program.clone()).run(vec![8]).0, vec![1000]);
-        assert_eq!(Computer::load(program.clone()).run(vec![9]).0, vec![1001]);
+        assert_eq!(
+            Computer::load(program.clone()).run(vec![7]).output,
+            vec![999]
+        );
+        assert_eq!(
+            Computer::load(program.clone()).run(vec![8]).output,
+            vec![1000]
+        );
+        assert_eq!(
+            Computer::load(program.clone()).run(vec![9]).output,
+            vec![1001]
+        );
 
         let program = vec![
             109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
         ];
         assert_eq!(
-            Computer::load(program.clone()).run(vec![]).0,
+            Computer::load(program.clone()).run(vec![]).output,
             program.clone()
         );
 
         let out = Computer::load(vec![1102, 34915192, 34915192, 7, 4, 7, 99, 0])
             .run(vec![])
-            .0;
+            .output;
         assert_eq!(out[0], 1_219_070_632_396_864);
     }
 
@@ -686,6 +1173,99 @@ mod computer_tests {
         ];
         let mut computer = Computer::load(program);
         computer.set_address(3, 123);
-        assert_eq!(computer.run(vec![]).0, vec![123]);
+        assert_eq!(computer.run(vec![]).output, vec![123]);
+    }
+
+    #[test]
+    fn refuses_to_run_after_halting() {
+        let mut computer = Computer::load(vec![104, 1, 99, 104, 666, 99]);
+        let result = computer.run(vec![]);
+        assert_eq!(result.output, vec![1]);
+        assert_eq!(result.instructions, 2);
+        assert!(computer.is_halted());
+        for _ in 0..2 {
+            let result = computer.run(vec![]);
+            assert_eq!(result.output, vec![]);
+            assert_eq!(result.stop, StopReason::Fault(Fault::AlreadyHalted));
+            assert!(computer.is_halted());
+        }
+    }
+
+    #[test]
+    fn stops_at_breakpoints() {
+        let program: Vec<i64> = vec![
+            1_04, // 0: output
+            1,    // 1: 1
+            1_04, // 2: output
+            2,    // 3: 2
+            99,   // 4: halt
+        ];
+        let mut computer = Computer::load(program);
+        computer.set_breakpoint(2);
+        let result = computer.run(vec![]);
+        assert_eq!(result.output, vec![1]);
+        assert_eq!(result.stop, StopReason::Breakpoint(2));
+        let result = computer.run(vec![]);
+        assert_eq!(result.output, vec![2]);
+        assert_eq!(result.stop, StopReason::Halted);
+    }
+
+    #[test]
+    fn stops_when_instruction_budget_is_exhausted() {
+        let program: Vec<i64> = vec![
+            1_04,  // 0: output
+            7,     // 1: 7
+            11_05, // 2: jump if true
+            1,     // 3: true
+            0,     // 4: to @0
+        ];
+        let mut computer = Computer::load(program);
+        computer.set_instruction_budget(Some(5));
+        let result = computer.run(vec![]);
+        assert_eq!(result.output, vec![7, 7, 7]);
+        assert_eq!(result.instructions, 5);
+        assert_eq!(result.stop, StopReason::BudgetExhausted);
+        assert_eq!(computer.run(vec![]).output, vec![7, 7]);
+    }
+
+    #[test]
+    fn reports_faults() {
+        let fault = |program: Vec<i64>| match Computer::load(program).run(vec![]).stop {
+            StopReason::Fault(fault) => fault,
+            stop => panic!("expected fault, got {:?}", stop),
+        };
+        assert_eq!(
+            fault(vec![1_04, 5, 42]),
+            Fault::InvalidOpcode { addr: 2, value: 42 }
+        );
+        assert_eq!(
+            fault(vec![3_04, 5, 99]),
+            Fault::InvalidMode {
+                addr: 0,
+                value: 304
+            }
+        );
+        assert_eq!(
+            fault(vec![111_01, 1, 2, 3, 99]),
+            Fault::ImmediateDestination { addr: 0 }
+        );
+        assert_eq!(
+            fault(vec![4, -1, 99]),
+            Fault::AddressOutOfRange {
+                addr: 0,
+                address: -1
+            }
+        );
+        assert_eq!(
+            fault(vec![3, 10_000, 99]),
+            Fault::AddressOutOfRange {
+                addr: 0,
+                address: 10_000
+            }
+        );
+        assert_eq!(
+            Fault::InvalidOpcode { addr: 2, value: 42 }.to_string(),
+            "invalid opcode 42 at address 2"
+        );
     }
 }
@@ -0,0 +1,287 @@
+use crate::decompile::{classify, Kind, Param};
+use crate::{Mode, Opcode};
+use num_traits::FromPrimitive;
+use std::collections::BTreeSet;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Check {
+    InvalidOpcode,
+    InvalidMode,
+    ImmediateDestination,
+    JumpOutOfRange,
+    ReadOutOfRange,
+    RunsPastEnd,
+}
+
+impl Check {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Check::InvalidOpcode => "invalid-opcode",
+            Check::InvalidMode => "invalid-mode",
+            Check::ImmediateDestination => "immediate-destination",
+            Check::JumpOutOfRange => "jump-out-of-range",
+            Check::ReadOutOfRange => "read-out-of-range",
+            Check::RunsPastEnd => "runs-past-end",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub addr: usize,
+    pub severity: Severity,
+    pub check: Check,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error(addr: usize, check: Check, message: String) -> Self {
+        Self {
+            addr,
+            severity: Severity::Error,
+            check,
+            message,
+        }
+    }
+
+    fn warning(addr: usize, check: Check, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(addr, check, message)
+        }
+    }
+}
+
+/// Formatted as `addr:severity:check: message`, one diagnostic per line.
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.addr,
+            self.severity,
+            self.check.name(),
+            self.message
+        )
+    }
+}
+
+// Checks the encoding of the instruction at `addr`, returning its opcode if it is safe to
+// follow control flow from it.
+fn check_instruction(program: &[i64], addr: usize, out: &mut Vec<Diagnostic>) -> Option<Opcode> {
+    let value = program[addr];
+    let opcode: Opcode = match FromPrimitive::from_i64(value % 100) {
+        Some(opcode) => opcode,
+        None => {
+            let message = format!("{} is not a valid instruction", value);
+            out.push(Diagnostic::error(addr, Check::InvalidOpcode, message));
+            return None;
+        }
+    };
+    let mut valid = true;
+    if addr + opcode.num_params() >= program.len() {
+        let message = format!(
+            "{} needs {} parameters but the program ends at {}",
+            opcode.mnemonic(),
+            opcode.num_params(),
+            program.len()
+        );
+        out.push(Diagnostic::error(addr, Check::RunsPastEnd, message));
+        valid = false;
+    }
+    let mut modes = value / 100;
+    for i in 0..opcode.num_params() {
+        let mode: Option<Mode> = FromPrimitive::from_i64(modes % 10);
+        modes /= 10;
+        let raw = *program.get(addr + 1 + i).unwrap_or(&0);
+        let writes = opcode.dest_param() == Some(i);
+        match mode {
+            None => {
+                let message = format!("{} has an invalid mode for parameter {}", value, i + 1);
+                out.push(Diagnostic::error(addr, Check::InvalidMode, message));
+                return None;
+            }
+            Some(Mode::Immediate) if writes => {
+                let message = format!(
+                    "{} writes to immediate mode parameter {}",
+                    opcode.mnemonic(),
+                    i + 1
+                );
+                out.push(Diagnostic::error(
+                    addr,
+                    Check::ImmediateDestination,
+                    message,
+                ));
+                valid = false;
+            }
+            Some(Mode::Position) if raw < 0 => {
+                let message = format!("{} accesses negative address {}", opcode.mnemonic(), raw);
+                out.push(Diagnostic::error(addr, Check::ReadOutOfRange, message));
+            }
+            Some(Mode::Position) if !writes && raw as usize >= program.len() => {
+                let message = format!(
+                    "{} reads @{} beyond the end of the program at {}",
+                    opcode.mnemonic(),
+                    raw,
+                    program.len()
+                );
+                out.push(Diagnostic::warning(addr, Check::ReadOutOfRange, message));
+            }
+            _ => {}
+        }
+    }
+    if modes != 0 {
+        let message = format!("{} has more mode digits than parameters", value);
+        out.push(Diagnostic::error(addr, Check::InvalidMode, message));
+        return None;
+    }
+    if valid {
+        Some(opcode)
+    } else {
+        None
+    }
+}
+
+/// Statically checks the code reachable from address 0, following constant jumps and the
+/// call convention recognised by the decompiler. Jumps to computed addresses are not
+/// followed. Diagnostics are ordered by address.
+pub fn lint(program: &[i64]) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let mut seen = BTreeSet::new();
+    let mut pending = vec![0];
+    while let Some(addr) = pending.pop() {
+        if addr >= program.len() || !seen.insert(addr) {
+            continue;
+        }
+        if check_instruction(program, addr, &mut diagnostics).is_none() {
+            continue;
+        }
+        let inst = classify(program, addr);
+        let target = match inst.kind {
+            Kind::Jump(Param::Imm(t)) | Kind::Branch(_, _, Param::Imm(t)) => Some(t),
+            Kind::Call(t) => Some(t as i64),
+            _ => None,
+        };
+        if let Some(t) = target {
+            if t < 0 || t as usize >= program.len() {
+                let message = format!("jump to {} outside the program", t);
+                diagnostics.push(Diagnostic::error(addr, Check::JumpOutOfRange, message));
+            } else {
+                pending.push(t as usize);
+            }
+        }
+        for next in inst.successors() {
+            if next >= program.len() && Some(next as i64) != target {
+                let message = "execution runs off the end of the program".to_owned();
+                diagnostics.push(Diagnostic::error(addr, Check::RunsPastEnd, message));
+            }
+            pending.push(next);
+        }
+    }
+    diagnostics.sort_by_key(|d| d.addr);
+    diagnostics
+}
+
+#[cfg(test)]
+#[allow(clippy::inconsistent_digit_grouping)]
+mod lint_tests {
+    use super::*;
+
+    fn checks(program: &[i64]) -> Vec<(usize, Check)> {
+        lint(program).iter().map(|d| (d.addr, d.check)).collect()
+    }
+
+    #[test]
+    fn accepts_puzzle_programs() {
+        for day in &["09", "13", "15", "17", "19", "21", "23", "25"] {
+            let program: Vec<i64> = std::fs::read_to_string(format!("../../day-{}/input", day))
+                .unwrap()
+                .trim()
+                .split(',')
+                .map(|s| s.parse().unwrap())
+                .collect();
+            let errors: Vec<Diagnostic> = lint(&program)
+                .into_iter()
+                .filter(|d| d.severity == Severity::Error)
+                .collect();
+            assert_eq!(errors, vec![], "day {}", day);
+        }
+    }
+
+    #[test]
+    fn flags_bad_encodings() {
+        let program: Vec<i64> = vec![
+            111_01, // 0: add
+            1,      // 1: 1
+            2,      // 2: 2
+            3,      // 3: ... to immediate 3
+            99,     // 4: halt
+        ];
+        assert_eq!(checks(&program), vec![(0, Check::ImmediateDestination)]);
+        assert_eq!(checks(&[2, 0, 0]), vec![(0, Check::RunsPastEnd)]);
+        assert_eq!(checks(&[1_04, 0, 42]), vec![(2, Check::InvalidOpcode)]);
+        assert_eq!(checks(&[3_04, 0, 99]), vec![(0, Check::InvalidMode)]);
+        assert_eq!(checks(&[1_00_04, 0, 99]), vec![(0, Check::InvalidMode)]);
+    }
+
+    #[test]
+    fn flags_jumps_and_reads_out_of_range() {
+        let program: Vec<i64> = vec![
+            4,     // 0: output
+            100,   // 1: @100
+            4,     // 2: output
+            -1,    // 3: @-1
+            3,     // 4: input
+            50,    // 5: ... to @50
+            10_05, // 6: jump if true
+            50,    // 7: @50
+            20,    // 8: to @20
+            11_06, // 9: jump if false
+            0,     // 10: false
+            -3,    // 11: to @-3
+        ];
+        let diagnostics = lint(&program);
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "0:warning:read-out-of-range: out reads @100 beyond the end of the program at 12",
+                "2:error:read-out-of-range: out accesses negative address -1",
+                "6:warning:read-out-of-range: jt reads @50 beyond the end of the program at 12",
+                "6:error:jump-out-of-range: jump to 20 outside the program",
+                "9:error:jump-out-of-range: jump to -3 outside the program",
+            ]
+        );
+    }
+
+    #[test]
+    fn follows_only_reachable_code() {
+        let program: Vec<i64> = vec![
+            11_05, // 0: jump if true
+            1,     // 1: true
+            4,     // 2: to @4
+            42,    // 3: data
+            1_04,  // 4: output
+            0,     // 5: 0
+        ];
+        assert_eq!(checks(&program), vec![(4, Check::RunsPastEnd)]);
+    }
+}
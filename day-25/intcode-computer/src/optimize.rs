@@ -0,0 +1,733 @@
+use crate::decode;
+use crate::decompile::{analyse, Kind, Param};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+#[derive(Clone, Debug)]
+struct Instr {
+    opcode: i64,
+    params: Vec<Param>,
+    // Instructions whose words are also accessed as data are never changed or moved.
+    pinned: bool,
+    removed: bool,
+}
+
+impl Instr {
+    fn new(program: &[i64], addr: usize) -> Option<Self> {
+        let (_, params) = decode(program, addr)?;
+        Some(Self {
+            opcode: program[addr] % 100,
+            params: params.iter().map(Param::new).collect(),
+            pinned: false,
+            removed: false,
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.params.len() + 1
+    }
+
+    fn encode(&self) -> Vec<i64> {
+        let mut words = vec![self.opcode];
+        let mut scale = 100;
+        for param in &self.params {
+            let (mode, v) = match *param {
+                Param::Pos(v) => (0, v),
+                Param::Imm(v) => (1, v),
+                Param::Rel(v) => (2, v),
+            };
+            words[0] += mode * scale;
+            scale *= 10;
+            words.push(v);
+        }
+        words
+    }
+
+    fn is_jump(&self) -> bool {
+        self.opcode == 5 || self.opcode == 6
+    }
+
+    // Whether a jump is taken, when that doesn't depend on memory.
+    fn taken(&self) -> Option<bool> {
+        match (self.opcode, self.params.first()) {
+            (5, Some(Param::Imm(v))) => Some(*v != 0),
+            (6, Some(Param::Imm(v))) => Some(*v == 0),
+            _ => None,
+        }
+    }
+
+    fn target(&self) -> Option<usize> {
+        match self.params.get(1) {
+            Some(Param::Imm(t)) if self.is_jump() && *t >= 0 => Some(*t as usize),
+            _ => None,
+        }
+    }
+
+    fn ends_block(&self) -> bool {
+        self.is_jump() || self.opcode == 99
+    }
+
+    // True if execution never continues with the following instruction.
+    fn terminates(&self, addr: usize) -> bool {
+        self.opcode == 99
+            || (self.taken() == Some(true) && self.target() != Some(addr + self.len()))
+    }
+
+    fn dest(&self) -> Option<usize> {
+        match self.opcode {
+            1 | 2 | 7 | 8 => Some(2),
+            3 => Some(0),
+            _ => None,
+        }
+    }
+
+    fn dest_param(&self) -> Option<Param> {
+        self.dest().map(|i| self.params[i])
+    }
+
+    fn reads(&self) -> impl Iterator<Item = &Param> {
+        let dest = self.dest();
+        self.params
+            .iter()
+            .enumerate()
+            .filter(move |(i, _)| Some(*i) != dest)
+            .map(|(_, p)| p)
+    }
+
+    fn constant(&self) -> Option<i64> {
+        let (a, b) = match (self.params.first(), self.params.get(1)) {
+            (Some(Param::Imm(a)), Some(Param::Imm(b))) => (*a, *b),
+            _ => return None,
+        };
+        match self.opcode {
+            1 => Some(a + b),
+            2 => Some(a * b),
+            7 => Some((a < b) as i64),
+            8 => Some((a == b) as i64),
+            _ => None,
+        }
+    }
+}
+
+struct Optimizer<'a> {
+    program: &'a [i64],
+    instrs: BTreeMap<usize, Instr>,
+    call_sites: BTreeSet<usize>,
+    blocks: Vec<Vec<usize>>,
+    // Relative accesses can't touch the program's code, though they can still reach data
+    // cells past its end.
+    stack_outside_program: bool,
+    relocatable: bool,
+}
+
+impl<'a> Optimizer<'a> {
+    fn new(program: &'a [i64]) -> Option<Self> {
+        let analysis = analyse(program);
+        let mut instrs = BTreeMap::new();
+        let mut call_sites = BTreeSet::new();
+        for (&addr, inst) in &analysis.instructions {
+            match inst.kind {
+                // Whatever runs here is only known once the program has modified itself.
+                Kind::Invalid(_) => return None,
+                Kind::Call(_) => {
+                    call_sites.insert(addr);
+                    instrs.insert(addr + 4, Instr::new(program, addr + 4)?);
+                }
+                _ => {}
+            }
+            instrs.insert(addr, Instr::new(program, addr)?);
+        }
+
+        let mut end = 0;
+        for (&addr, instr) in &instrs {
+            if addr < end {
+                // Overlapping instructions can't be rewritten independently.
+                return None;
+            }
+            end = addr + instr.len();
+        }
+
+        let data: BTreeSet<i64> = instrs
+            .values()
+            .flat_map(|i| &i.params)
+            .filter_map(|p| match p {
+                Param::Pos(a) => Some(*a),
+                _ => None,
+            })
+            .collect();
+        let uses_stack = instrs
+            .values()
+            .any(|i| i.params.iter().any(|p| matches!(p, Param::Rel(_))));
+        let stack_outside_program = !uses_stack
+            || matches!(instrs.get(&0), Some(Instr { opcode: 9, params, .. })
+                if matches!(params[0], Param::Imm(n) if n >= program.len() as i64));
+        // Any word of the program may be read or written through the relative base.
+        for (&addr, instr) in instrs.iter_mut() {
+            instr.pinned = !stack_outside_program
+                || (addr..addr + instr.len()).any(|a| data.contains(&(a as i64)));
+        }
+
+        let mut optimizer = Self {
+            program,
+            instrs,
+            call_sites,
+            blocks: vec![],
+            stack_outside_program,
+            relocatable: stack_outside_program && analysis.unresolved_jumps.is_empty(),
+        };
+        optimizer.find_blocks();
+        Some(optimizer)
+    }
+
+    fn find_blocks(&mut self) {
+        let mut leaders: BTreeSet<usize> = BTreeSet::new();
+        leaders.insert(0);
+        for (&addr, instr) in &self.instrs {
+            leaders.extend(instr.target());
+            if instr.ends_block() {
+                leaders.insert(addr + instr.len());
+            }
+        }
+        for &leader in &leaders {
+            let mut block = vec![];
+            let mut addr = leader;
+            while let Some(instr) = self.instrs.get(&addr) {
+                block.push(addr);
+                addr += instr.len();
+                if instr.ends_block() || leaders.contains(&addr) {
+                    break;
+                }
+            }
+            if !block.is_empty() {
+                self.blocks.push(block);
+            }
+        }
+    }
+
+    // Evaluates arithmetic on immediates and substitutes cells that were set to a constant
+    // earlier in the same block.
+    fn fold_constants(&mut self) {
+        for block in &self.blocks {
+            let mut known: HashMap<i64, i64> = HashMap::new();
+            for addr in block {
+                let instr = self.instrs.get_mut(addr).unwrap();
+                if instr.pinned {
+                    known.clear();
+                    continue;
+                }
+                let dest = instr.dest();
+                for (i, param) in instr.params.iter_mut().enumerate() {
+                    if let Param::Pos(c) = *param {
+                        if Some(i) != dest {
+                            if let Some(&v) = known.get(&c) {
+                                *param = Param::Imm(v);
+                            }
+                        }
+                    }
+                }
+                let constant = instr.constant();
+                if let Some(v) = constant {
+                    instr.opcode = 1;
+                    instr.params = vec![Param::Imm(v), Param::Imm(0), instr.params[2]];
+                }
+                // Any cell may be read or written through the relative base.
+                if instr.params.iter().any(|p| matches!(p, Param::Rel(_))) {
+                    known.clear();
+                }
+                if let Some(Param::Pos(c)) = instr.dest_param() {
+                    match constant {
+                        Some(v) => {
+                            known.insert(c, v);
+                        }
+                        None => {
+                            known.remove(&c);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn simplify_jumps(&mut self) {
+        for instr in self.instrs.values_mut() {
+            if !instr.is_jump() || instr.pinned {
+                continue;
+            }
+            match instr.taken() {
+                Some(false) => instr.removed = true,
+                Some(true) => {
+                    instr.opcode = 5;
+                    instr.params[0] = Param::Imm(1);
+                }
+                None => {}
+            }
+        }
+
+        // Jumps to unconditional jumps go straight to the final destination.
+        let mut retargets = vec![];
+        for (&addr, instr) in &self.instrs {
+            let mut target = match instr.target() {
+                Some(t) if !instr.pinned => t,
+                _ => continue,
+            };
+            let mut seen = BTreeSet::new();
+            while let Some(next) = self.instrs.get(&target) {
+                if next.pinned || next.removed || next.taken() != Some(true) {
+                    break;
+                }
+                match next.target() {
+                    Some(t) if seen.insert(target) => target = t,
+                    _ => break,
+                }
+            }
+            retargets.push((addr, target));
+        }
+        for (addr, target) in retargets {
+            let instr = self.instrs.get_mut(&addr).unwrap();
+            instr.params[1] = Param::Imm(target as i64);
+            if instr.target() == Some(addr + instr.len()) {
+                instr.removed = true;
+            }
+        }
+    }
+
+    // Removes arithmetic whose result is overwritten later in the same block before
+    // anything reads it.
+    fn remove_dead_writes(&mut self) {
+        if !self.stack_outside_program {
+            return;
+        }
+        let code: BTreeSet<i64> = self
+            .instrs
+            .iter()
+            .flat_map(|(&addr, i)| (addr..addr + i.len()).map(|a| a as i64))
+            .collect();
+        let mut dead = vec![];
+        for block in &self.blocks {
+            for (i, addr) in block.iter().enumerate() {
+                let instr = &self.instrs[addr];
+                if instr.pinned || instr.removed || instr.opcode == 3 {
+                    continue;
+                }
+                let cell = match instr.dest_param() {
+                    Some(Param::Pos(c)) if !code.contains(&c) => Param::Pos(c),
+                    _ => continue,
+                };
+                for later in &block[i + 1..] {
+                    let later = &self.instrs[later];
+                    if later.removed {
+                        continue;
+                    }
+                    // Anything may be accessed through the relative base.
+                    let uses_stack = later.params.iter().any(|p| matches!(p, Param::Rel(_)));
+                    if later.pinned || uses_stack || later.reads().any(|&p| p == cell) {
+                        break;
+                    }
+                    if later.dest_param() == Some(cell) {
+                        dead.push(*addr);
+                        break;
+                    }
+                }
+            }
+        }
+        for addr in dead {
+            self.instrs.get_mut(&addr).unwrap().removed = true;
+        }
+    }
+
+    // Splits the code into runs of contiguous instructions. Only runs that end without
+    // falling through can shrink, since the space freed at the end is never executed.
+    fn segments(&self) -> Vec<(Vec<usize>, bool)> {
+        let mut segments = vec![];
+        let mut current: Vec<usize> = vec![];
+        let mut end = 0;
+        for (&addr, instr) in &self.instrs {
+            if !current.is_empty() && addr != end {
+                segments.push((current, false));
+                current = vec![];
+            }
+            current.push(addr);
+            end = addr + instr.len();
+            if !instr.removed && instr.terminates(addr) {
+                segments.push((current, true));
+                current = vec![];
+            }
+        }
+        if !current.is_empty() {
+            segments.push((current, false));
+        }
+        for (segment, compact) in segments.iter_mut() {
+            *compact &= self.relocatable && segment.iter().all(|a| !self.instrs[a].pinned);
+        }
+        segments
+    }
+
+    fn relocations(&self, segments: &[(Vec<usize>, bool)]) -> BTreeMap<usize, usize> {
+        let mut map = BTreeMap::new();
+        for (segment, _) in segments.iter().filter(|(_, compact)| *compact) {
+            let mut cursor = segment[0];
+            let mut pending = vec![];
+            for addr in segment {
+                let instr = &self.instrs[addr];
+                pending.push(*addr);
+                if !instr.removed {
+                    for a in pending.drain(..) {
+                        map.insert(a, cursor);
+                    }
+                    cursor += instr.len();
+                }
+            }
+        }
+        map
+    }
+
+    // The code addresses an instruction refers to: its jump target, or the return address
+    // stored by a call.
+    fn code_reference(&self, addr: usize) -> Option<usize> {
+        let instr = &self.instrs[&addr];
+        if self.call_sites.contains(&addr) {
+            return instr.constant().map(|r| r as usize);
+        }
+        instr.target()
+    }
+
+    fn optimize(mut self) -> Vec<i64> {
+        self.fold_constants();
+        self.simplify_jumps();
+        self.remove_dead_writes();
+
+        let mut segments = self.segments();
+        let mut map = self.relocations(&segments);
+        // Pinned instructions can't be updated to follow code that moves.
+        loop {
+            let stuck: BTreeSet<usize> = self
+                .instrs
+                .iter()
+                .filter(|(_, i)| i.pinned)
+                .filter_map(|(&addr, _)| self.code_reference(addr))
+                .filter(|t| map.get(t).is_some_and(|n| n != t))
+                .collect();
+            if stuck.is_empty() {
+                break;
+            }
+            for (segment, compact) in segments.iter_mut() {
+                if segment.iter().any(|a| stuck.contains(a)) {
+                    *compact = false;
+                }
+            }
+            map = self.relocations(&segments);
+        }
+
+        let mut out = self.program.to_vec();
+        for (segment, compact) in &segments {
+            let mut cursor = segment[0];
+            for addr in segment {
+                let instr = &self.instrs[addr];
+                if instr.pinned || (*compact && instr.removed) {
+                    continue;
+                }
+                let mut instr = instr.clone();
+                if let Some(t) = self.code_reference(*addr) {
+                    let t = *map.get(&t).unwrap_or(&t) as i64;
+                    if self.call_sites.contains(addr) {
+                        instr.opcode = 1;
+                        instr.params = vec![Param::Imm(t), Param::Imm(0), instr.params[2]];
+                    } else {
+                        instr.params[1] = Param::Imm(t);
+                    }
+                }
+                if !*compact {
+                    cursor = *addr;
+                }
+                let words = if *compact || !instr.removed {
+                    instr.encode()
+                } else {
+                    self.program[*addr..*addr + instr.len()].to_vec()
+                };
+                out[cursor..cursor + words.len()].copy_from_slice(&words);
+                cursor += words.len();
+            }
+            if *compact {
+                let last = segment[segment.len() - 1];
+                let end = last + self.instrs[&last].len();
+                for word in &mut out[cursor..end] {
+                    *word = 0;
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Rewrites `program` into an equivalent one that executes fewer instructions: arithmetic
+/// on immediates is folded, constants are propagated within basic blocks, jumps that are
+/// always or never taken are simplified, chains of jumps are threaded and writes that are
+/// overwritten before being read are dropped. Instructions that are read or written as
+/// data are left untouched. Code is only compacted when every jump target is known, and
+/// data never moves. Programs that can reach an invalid instruction (usually because it is
+/// only written at run time) or whose instructions overlap are returned unchanged.
+pub fn optimize(program: &[i64]) -> Vec<i64> {
+    match Optimizer::new(program) {
+        Some(optimizer) => optimizer.optimize(),
+        None => program.to_vec(),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::inconsistent_digit_grouping)]
+mod optimize_tests {
+    use super::*;
+    use crate::Computer;
+
+    fn read_program(path: &str) -> Vec<i64> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .trim()
+            .split(',')
+            .map(|s| s.parse().unwrap())
+            .collect()
+    }
+
+    fn ascii(s: &str) -> Vec<i64> {
+        s.chars().map(|c| c as i64).collect()
+    }
+
+    fn instructions_executed(computer: &Computer) -> u64 {
+        let coverage = computer.coverage().unwrap();
+        coverage
+            .executed_addresses()
+            .map(|a| coverage.hits(a))
+            .sum()
+    }
+
+    // Runs each sequence of inputs against the original and optimized programs, checking
+    // they produce the same results and returning the instructions each executed.
+    fn compare(program: &[i64], patches: &[(usize, i64)], runs: &[Vec<Vec<i64>>]) -> (u64, u64) {
+        let optimized = optimize(program);
+        assert_eq!(optimized.len(), program.len());
+        let mut counts = (0, 0);
+        for inputs in runs {
+            let mut original = Computer::load(program.to_vec());
+            let mut fast = Computer::load(optimized.clone());
+            for &(addr, value) in patches {
+                original.set_address(addr, value);
+                fast.set_address(addr, value);
+            }
+            original.enable_coverage();
+            fast.enable_coverage();
+            for input in inputs {
+                let expected = original.run(input.clone());
+                let actual = fast.run(input.clone());
+                assert_eq!(actual.output, expected.output);
+                assert_eq!(actual.stop, expected.stop);
+                if expected.is_halted() {
+                    break;
+                }
+            }
+            counts.0 += instructions_executed(&original);
+            counts.1 += instructions_executed(&fast);
+        }
+        assert!(counts.1 <= counts.0);
+        counts
+    }
+
+    #[test]
+    fn folds_constants_and_drops_dead_code() {
+        let program: Vec<i64> = vec![
+            11_01, // 0: add
+            2,     // 1: 2
+            3,     // 2: 3
+            23,    // 3: ... to @23
+            10_02, // 4: multiply
+            23,    // 5: @23
+            4,     // 6: 4
+            24,    // 7: ... to @24
+            11_01, // 8: add
+            7,     // 9: 7
+            0,     // 10: 0
+            23,    // 11: ... to @23
+            11_06, // 12: jump if false
+            1,     // 13: true
+            0,     // 14: to @0 (never taken)
+            11_05, // 15: jump if true
+            1,     // 16: true
+            18,    // 17: to @18 (the next instruction)
+            4,     // 18: output
+            24,    // 19: @24
+            4,     // 20: output
+            23,    // 21: @23
+            99,    // 22: halt
+            0,     // 23
+            0,     // 24
+        ];
+        let optimized = optimize(&program);
+        assert_eq!(
+            optimized,
+            vec![
+                11_01, 20, 0, 24, // 0: add 20, 0 to @24
+                11_01, 7, 0, 23, // 4: add 7, 0 to @23
+                4, 24, // 8: output @24
+                4, 23, // 10: output @23
+                99, // 12: halt
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // 13: freed
+                0, 0, // 23: data
+            ]
+        );
+        assert_eq!(
+            Computer::load(optimized).run(vec![]).output,
+            Computer::load(program).run(vec![]).output
+        );
+    }
+
+    #[test]
+    fn keeps_writes_read_through_relative_base() {
+        let program: Vec<i64> = vec![
+            1_09,  // 0: adjust relative base
+            50,    // 1: by 50
+            11_01, // 2: add
+            7,     // 3: 7
+            0,     // 4: 0
+            60,    // 5: ... to @60
+            2_04,  // 6: output
+            10,    // 7: @rb+10, which is @60
+            11_01, // 8: add
+            8,     // 9: 8
+            0,     // 10: 0
+            60,    // 11: ... to @60
+            4,     // 12: output
+            60,    // 13: @60
+            99,    // 14: halt
+        ];
+        let optimized = optimize(&program);
+        assert_eq!(Computer::load(program).run(vec![]).output, vec![7, 8]);
+        assert_eq!(Computer::load(optimized).run(vec![]).output, vec![7, 8]);
+    }
+
+    #[test]
+    fn leaves_code_alone_when_the_stack_overlaps_it() {
+        for offset in 0..12 {
+            let program: Vec<i64> = vec![
+                2_04,   // 0: output
+                offset, // 1: @rb+offset, which may be code
+                11_01,  // 2: add
+                2,      // 3: 2
+                3,      // 4: 3
+                9,      // 5: ... to @9
+                4,      // 6: output
+                9,      // 7: @9
+                99,     // 8: halt
+                0,      // 9
+            ];
+            let optimized = optimize(&program);
+            assert_eq!(
+                Computer::load(optimized).run(vec![]).output,
+                Computer::load(program).run(vec![]).output
+            );
+        }
+    }
+
+    #[test]
+    fn threads_jumps_through_unconditional_jumps() {
+        let program: Vec<i64> = vec![
+            3,     // 0: input
+            15,    // 1: ... to @15
+            10_05, // 2: jump if true
+            15,    // 3: @15
+            8,     // 4: to @8
+            1_04,  // 5: output
+            0,     // 6: 0
+            99,    // 7: halt
+            11_05, // 8: jump if true
+            1,     // 9: true
+            11,    // 10: to @11
+            1_04,  // 11: output
+            1,     // 12: 1
+            99,    // 13: halt
+            0,     // 14
+            0,     // 15
+        ];
+        let optimized = optimize(&program);
+        assert_eq!(&optimized[2..5], &[10_05, 15, 8]);
+        assert_eq!(&optimized[8..11], &[1_04, 1, 99]);
+        for input in 0..2 {
+            assert_eq!(
+                Computer::load(optimized.clone()).run(vec![input]).output,
+                Computer::load(program.clone()).run(vec![input]).output
+            );
+        }
+    }
+
+    #[test]
+    fn leaves_self_modifying_code_alone() {
+        let program: Vec<i64> = vec![
+            1_04,  // 0: output
+            7,     // 1: 7 (incremented on each pass)
+            10_01, // 2: add
+            1,     // 3: @1
+            1,     // 4: 1
+            1,     // 5: ... to @1
+            10_08, // 6: equals
+            1,     // 7: @1
+            9,     // 8: 9
+            14,    // 9: ... to @14
+            10_06, // 10: jump if false
+            14,    // 11: @14
+            0,     // 12: to @0
+            99,    // 13: halt
+            0,     // 14
+        ];
+        let optimized = optimize(&program);
+        assert_eq!(optimized, program);
+    }
+
+    #[test]
+    fn optimized_puzzle_programs_match_computer() {
+        let day_05 = read_program("../../day-05/input");
+        assert_eq!(optimize(&day_05), day_05);
+        compare(&day_05, &[], &[vec![vec![1]], vec![vec![5]]]);
+
+        let phases: Vec<Vec<Vec<i64>>> = (0..5).map(|p| vec![vec![p, 0]]).collect();
+        compare(&read_program("../../day-07/input"), &[], &phases);
+        compare(&read_program("../../day-09/input"), &[], &[vec![vec![1]]]);
+        compare(
+            &read_program("../../day-11/input"),
+            &[],
+            &[(0..50).map(|i| vec![i % 2]).collect()],
+        );
+        compare(&read_program("../../day-13/input"), &[], &[vec![vec![]]]);
+        compare(
+            &read_program("../../day-13/input"),
+            &[(0, 2)],
+            &[(0..50).map(|i| vec![i % 3 - 1]).collect()],
+        );
+        let (before, after) = compare(
+            &read_program("../../day-15/input"),
+            &[],
+            &[(0..200).map(|i| vec![(i * 7 % 4) + 1]).collect()],
+        );
+        assert!(after < before);
+        compare(&read_program("../../day-17/input"), &[], &[vec![vec![]]]);
+        let beam: Vec<Vec<Vec<i64>>> = (0..25).map(|i| vec![vec![i % 5, i / 5]]).collect();
+        compare(&read_program("../../day-19/input"), &[], &beam);
+        let (before, after) = compare(
+            &read_program("../../day-21/input"),
+            &[],
+            &[vec![ascii("NOT A J\nNOT C T\nOR T J\nAND D J\nWALK\n")]],
+        );
+        assert!(after < before);
+        compare(
+            &read_program("../../day-23/input"),
+            &[],
+            &[vec![vec![3], vec![-1], vec![-1]]],
+        );
+        compare(
+            &read_program("../../day-25/input"),
+            &[],
+            &[vec![
+                vec![],
+                ascii("north\n"),
+                ascii("inv\n"),
+                ascii("south\n"),
+            ]],
+        );
+    }
+}
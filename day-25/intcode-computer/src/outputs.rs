@@ -0,0 +1,194 @@
+use crate::{Computer, Fault, StopReason};
+use std::collections::VecDeque;
+use std::fmt;
+
+/// Yields a `Computer`'s output values as they are produced. Iteration ends when the
+/// computer stops for any reason, which is then available from `stop`.
+pub struct Outputs<'a> {
+    computer: &'a mut Computer,
+    pending: VecDeque<i64>,
+    stop: Option<StopReason>,
+}
+
+/// The values left over when the output ends part way through a chunk.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PartialChunk {
+    pub size: usize,
+    pub values: Vec<i64>,
+}
+
+impl fmt::Display for PartialChunk {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "output ended with {} of {} values in a chunk: {:?}",
+            self.values.len(),
+            self.size,
+            self.values
+        )
+    }
+}
+
+impl<'a> Outputs<'a> {
+    pub(crate) fn new(computer: &'a mut Computer) -> Self {
+        let stop = if computer.halted {
+            Some(StopReason::Fault(Fault::AlreadyHalted))
+        } else {
+            None
+        };
+        Self {
+            computer,
+            pending: VecDeque::new(),
+            stop,
+        }
+    }
+
+    /// Why the computer stopped, once the iterator is exhausted.
+    pub fn stop(&self) -> Option<&StopReason> {
+        self.stop.as_ref()
+    }
+
+    /// Groups the output into chunks of `size` values.
+    pub fn chunks(self, size: usize) -> Chunks<'a> {
+        assert!(size > 0, "chunk size must be positive");
+        Chunks {
+            outputs: self,
+            size,
+        }
+    }
+}
+
+impl Iterator for Outputs<'_> {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<i64> {
+        if self.pending.is_empty() && self.stop.is_none() {
+            let stop = self.computer.execute(true);
+            self.pending.extend(self.computer.output.drain(..));
+            if let Some(stop) = stop {
+                self.computer.halted = stop == StopReason::Halted;
+                self.stop = Some(stop);
+            }
+        }
+        self.pending.pop_front()
+    }
+}
+
+pub struct Chunks<'a> {
+    outputs: Outputs<'a>,
+    size: usize,
+}
+
+impl Chunks<'_> {
+    pub fn stop(&self) -> Option<&StopReason> {
+        self.outputs.stop()
+    }
+}
+
+impl Iterator for Chunks<'_> {
+    type Item = Result<Vec<i64>, PartialChunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let values: Vec<i64> = self.outputs.by_ref().take(self.size).collect();
+        if values.is_empty() {
+            None
+        } else if values.len() < self.size {
+            Some(Err(PartialChunk {
+                size: self.size,
+                values,
+            }))
+        } else {
+            Some(Ok(values))
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::inconsistent_digit_grouping)]
+mod outputs_tests {
+    use super::*;
+
+    fn counter() -> Computer {
+        Computer::load(vec![
+            1_04,  // 0: output
+            0,     // 1: 0 (counter)
+            10_01, // 2: add
+            1,     // 3: @1
+            1,     // 4: 1
+            1,     // 5: ... to @1
+            11_05, // 6: jump if true
+            1,     // 7: true
+            0,     // 8: to @0
+        ])
+    }
+
+    #[test]
+    fn advances_only_as_far_as_needed() {
+        let mut computer = counter();
+        let first: Vec<i64> = computer.outputs(vec![]).take(3).collect();
+        assert_eq!(first, vec![0, 1, 2]);
+        let next: Vec<i64> = computer.outputs(vec![]).take(2).collect();
+        assert_eq!(next, vec![3, 4]);
+    }
+
+    #[test]
+    fn reports_why_output_ended() {
+        let mut computer = Computer::load(vec![
+            1_04, // 0: output
+            7,    // 1: 7
+            3,    // 2: input
+            9,    // 3: ... to @9
+            4,    // 4: output
+            9,    // 5: @9
+            99,   // 6: halt
+        ]);
+        let mut outputs = computer.outputs(vec![]);
+        assert_eq!(outputs.by_ref().collect::<Vec<_>>(), vec![7]);
+        assert_eq!(outputs.stop(), Some(&StopReason::NeedsInput));
+        let mut outputs = computer.outputs(vec![8]);
+        assert_eq!(outputs.by_ref().collect::<Vec<_>>(), vec![8]);
+        assert_eq!(outputs.stop(), Some(&StopReason::Halted));
+        assert!(computer.is_halted());
+    }
+
+    #[test]
+    fn yields_chunks() {
+        let mut computer = Computer::load(vec![
+            1_04, // 0: output
+            1,    // 1: 1
+            1_04, // 2: output
+            2,    // 3: 2
+            1_04, // 4: output
+            3,    // 5: 3
+            99,   // 6: halt
+        ]);
+        let mut chunks = computer.outputs(vec![]).chunks(2);
+        assert_eq!(chunks.next(), Some(Ok(vec![1, 2])));
+        let partial = chunks.next().unwrap().unwrap_err();
+        assert_eq!(
+            partial.to_string(),
+            "output ended with 1 of 2 values in a chunk: [3]"
+        );
+        assert_eq!(chunks.next(), None);
+        assert_eq!(chunks.stop(), Some(&StopReason::Halted));
+    }
+
+    #[test]
+    fn reads_arcade_tiles_in_triples() {
+        let mut computer = Computer::load_from_file("../../day-13/input");
+        let expected: Vec<Vec<i64>> = computer
+            .clone()
+            .run(vec![])
+            .output
+            .chunks(3)
+            .map(|tile| tile.to_vec())
+            .collect();
+        let tiles: Vec<Vec<i64>> = computer
+            .outputs(vec![])
+            .chunks(3)
+            .map(|tile| tile.unwrap())
+            .collect();
+        assert_eq!(tiles, expected);
+        assert!(computer.is_halted());
+    }
+}
@@ -0,0 +1,304 @@
+use crate::{Computer, Fault, StopReason};
+use std::collections::VecDeque;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome<T> {
+    /// The router asked to stop.
+    Stopped(T),
+    /// Every computer has halted.
+    Halted,
+    /// Every computer that hasn't halted is waiting for input that will never arrive.
+    Deadlock,
+    Fault(usize, Fault),
+    /// The router for the first computer sent a message to the second, which doesn't
+    /// exist. Messages before it in the outbox were delivered.
+    Undeliverable(usize, usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownComputer(pub usize);
+
+impl fmt::Display for UnknownComputer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "no computer with id {}", self.0)
+    }
+}
+
+/// Messages queued by the router for delivery once it returns.
+#[derive(Default)]
+pub struct Outbox {
+    messages: Vec<(usize, Vec<i64>)>,
+}
+
+impl Outbox {
+    pub fn send(&mut self, to: usize, values: &[i64]) {
+        self.messages.push((to, values.to_vec()));
+    }
+}
+
+struct Vm {
+    computer: Computer,
+    queue: VecDeque<i64>,
+    output: VecDeque<i64>,
+    halted: bool,
+}
+
+/// Runs many computers on one thread, giving each a slice of `slice` instructions per
+/// round or less if it blocks waiting for input.
+pub struct Scheduler {
+    vms: Vec<Vm>,
+    slice: u64,
+    idle_input: Option<i64>,
+}
+
+impl Scheduler {
+    pub fn new(slice: u64) -> Self {
+        Self {
+            vms: vec![],
+            slice,
+            idle_input: None,
+        }
+    }
+
+    /// Adds a computer and returns the id used to send it input.
+    pub fn add(&mut self, mut computer: Computer) -> usize {
+        computer.set_instruction_budget(Some(self.slice));
+        self.vms.push(Vm {
+            computer,
+            queue: VecDeque::new(),
+            output: VecDeque::new(),
+            halted: false,
+        });
+        self.vms.len() - 1
+    }
+
+    /// Gives a computer that asks for input when its queue is empty this value once per
+    /// slice, for programs that poll for input like the day 23 network. A computer still
+    /// counts as blocked if it asks again.
+    pub fn set_idle_input(&mut self, value: Option<i64>) {
+        self.idle_input = value;
+    }
+
+    pub fn send(&mut self, to: usize, values: &[i64]) -> Result<(), UnknownComputer> {
+        let vm = self.vms.get_mut(to).ok_or(UnknownComputer(to))?;
+        vm.queue.extend(values);
+        Ok(())
+    }
+
+    pub fn computer(&self, id: usize) -> &Computer {
+        &self.vms[id].computer
+    }
+
+    /// Runs rounds until the router returns a value or no computer can make progress. After
+    /// each slice that produced output, `router` is called with the id of the computer and
+    /// its pending output, and should remove whatever values it handles; anything left is
+    /// passed again with the next output, so partial messages can wait to be completed.
+    pub fn run<T, R>(&mut self, mut router: R) -> Outcome<T>
+    where
+        R: FnMut(usize, &mut VecDeque<i64>, &mut Outbox) -> Option<T>,
+    {
+        loop {
+            let mut quiet = true;
+            let mut blocked = true;
+            for id in 0..self.vms.len() {
+                let idle_input = self.idle_input;
+                let vm = &mut self.vms[id];
+                if vm.halted {
+                    continue;
+                }
+                let mut input: Vec<i64> = vm.queue.drain(..).collect();
+                let mut idle = false;
+                let waiting = loop {
+                    let given = if idle { 0 } else { input.len() };
+                    let result = vm.computer.run(input);
+                    vm.queue.extend(vm.computer.input.drain(..));
+                    if given > vm.queue.len() || !result.output.is_empty() {
+                        quiet = false;
+                    }
+                    vm.output.extend(result.output);
+                    match result.stop {
+                        StopReason::Halted => {
+                            vm.halted = true;
+                            break true;
+                        }
+                        StopReason::NeedsInput => match idle_input {
+                            Some(value) if !idle => {
+                                idle = true;
+                                input = vec![value];
+                            }
+                            _ => break true,
+                        },
+                        StopReason::Breakpoint(_) | StopReason::BudgetExhausted => break false,
+                        StopReason::Fault(fault) => return Outcome::Fault(id, fault),
+                    }
+                };
+                blocked &= waiting;
+                if vm.output.is_empty() {
+                    continue;
+                }
+                let mut outbox = Outbox::default();
+                let stop = router(id, &mut vm.output, &mut outbox);
+                for (to, values) in outbox.messages {
+                    if self.send(to, &values).is_err() {
+                        return Outcome::Undeliverable(id, to);
+                    }
+                }
+                if let Some(value) = stop {
+                    return Outcome::Stopped(value);
+                }
+            }
+            if self.vms.iter().all(|vm| vm.halted) {
+                return Outcome::Halted;
+            }
+            if quiet && blocked && self.vms.iter().all(|vm| vm.queue.is_empty()) {
+                return Outcome::Deadlock;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::inconsistent_digit_grouping)]
+mod scheduler_tests {
+    use super::*;
+
+    // Reads a value, outputs it plus one, and repeats.
+    fn incrementer() -> Computer {
+        Computer::load(vec![
+            3,     // 0: input
+            20,    // 1: ... to @20
+            10_01, // 2: add
+            20,    // 3: @20
+            1,     // 4: 1
+            20,    // 5: ... to @20
+            4,     // 6: output
+            20,    // 7: @20
+            11_05, // 8: jump if true
+            1,     // 9: true
+            0,     // 10: to @0
+        ])
+    }
+
+    #[test]
+    fn passes_values_around_a_ring() {
+        let mut scheduler = Scheduler::new(2);
+        for _ in 0..3 {
+            scheduler.add(incrementer());
+        }
+        scheduler.send(0, &[0]).unwrap();
+        let outcome = scheduler.run(|id, output, outbox| {
+            let value = output.pop_front().unwrap();
+            if value == 10 {
+                return Some((id, value));
+            }
+            outbox.send((id + 1) % 3, &[value]);
+            None
+        });
+        assert_eq!(outcome, Outcome::Stopped((0, 10)));
+    }
+
+    #[test]
+    fn detects_deadlock() {
+        let mut scheduler = Scheduler::new(100);
+        scheduler.add(incrementer());
+        scheduler.add(incrementer());
+        scheduler.send(0, &[5]).unwrap();
+        let mut seen = vec![];
+        let outcome: Outcome<()> = scheduler.run(|_, output, _| {
+            seen.extend(output.drain(..));
+            None
+        });
+        assert_eq!(outcome, Outcome::Deadlock);
+        assert_eq!(seen, vec![6]);
+    }
+
+    #[test]
+    fn reports_halting_and_faults() {
+        let mut scheduler = Scheduler::new(1);
+        scheduler.add(Computer::load(vec![1_04, 1, 1_04, 2, 99]));
+        let mut seen = vec![];
+        let outcome: Outcome<()> = scheduler.run(|_, output, _| {
+            seen.extend(output.drain(..));
+            None
+        });
+        assert_eq!(outcome, Outcome::Halted);
+        assert_eq!(seen, vec![1, 2]);
+
+        let mut scheduler = Scheduler::new(10);
+        scheduler.add(Computer::load(vec![99]));
+        scheduler.add(Computer::load(vec![42]));
+        let outcome: Outcome<()> = scheduler.run(|_, _, _| None);
+        assert_eq!(
+            outcome,
+            Outcome::Fault(1, Fault::InvalidOpcode { addr: 0, value: 42 })
+        );
+    }
+
+    #[test]
+    fn reports_messages_to_unknown_computers() {
+        let mut scheduler = Scheduler::new(100);
+        scheduler.add(incrementer());
+        assert_eq!(scheduler.send(1, &[0]), Err(UnknownComputer(1)));
+        scheduler.send(0, &[0]).unwrap();
+        let outcome: Outcome<()> = scheduler.run(|_, output, outbox| {
+            output.clear();
+            outbox.send(0, &[7]);
+            outbox.send(3, &[0]);
+            None
+        });
+        assert_eq!(outcome, Outcome::Undeliverable(0, 3));
+        assert_eq!(scheduler.vms[0].queue, vec![7]);
+    }
+
+    fn network() -> Scheduler {
+        let mut scheduler = Scheduler::new(1000);
+        scheduler.set_idle_input(Some(-1));
+        for addr in 0..50 {
+            let id = scheduler.add(Computer::load_from_file("../../day-23/input"));
+            scheduler.send(id, &[addr]).unwrap();
+        }
+        scheduler
+    }
+
+    // Moves complete (addr, x, y) packets to the outbox, returning those sent to 255.
+    fn route(output: &mut VecDeque<i64>, outbox: &mut Outbox) -> Option<(i64, i64)> {
+        let mut nat = None;
+        while output.len() >= 3 {
+            let packet: Vec<i64> = output.drain(..3).collect();
+            match packet[0] {
+                255 => nat = Some((packet[1], packet[2])),
+                addr => outbox.send(addr as usize, &packet[1..]),
+            }
+        }
+        nat
+    }
+
+    #[test]
+    fn runs_day_23_network() {
+        let mut scheduler = network();
+        match scheduler.run(|_, output, outbox| route(output, outbox)) {
+            Outcome::Stopped((_, y)) => assert_eq!(y, 26744),
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        }
+
+        let mut scheduler = network();
+        let mut nat = None;
+        let mut last_y = None;
+        let y = loop {
+            match scheduler.run(|_, output, outbox| route(output, outbox)) {
+                Outcome::Stopped(packet) => nat = Some(packet),
+                Outcome::Deadlock => {
+                    let (x, y) = nat.unwrap();
+                    if last_y == Some(y) {
+                        break y;
+                    }
+                    last_y = Some(y);
+                    scheduler.send(0, &[x, y]).unwrap();
+                }
+                outcome => panic!("unexpected outcome {:?}", outcome),
+            }
+        };
+        assert_eq!(y, 19498);
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::BTreeSet;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelfModification {
+    pub pc: usize,
+    pub address: usize,
+    pub old: i64,
+    pub new: i64,
+}
+
+/// Remembers which addresses have been executed as part of an instruction and flags any
+/// later write that changes one of them.
+#[derive(Clone, Debug, Default)]
+pub struct SelfModificationTracker {
+    code: BTreeSet<usize>,
+    pc: usize,
+    events: Vec<SelfModification>,
+}
+
+impl SelfModificationTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_instruction(&mut self, addr: usize, len: usize) {
+        self.pc = addr;
+        self.code.extend(addr..addr + len);
+    }
+
+    pub fn record_write(&mut self, address: usize, old: i64, new: i64) {
+        if old != new && self.code.contains(&address) {
+            self.events.push(SelfModification {
+                pc: self.pc,
+                address,
+                old,
+                new,
+            });
+        }
+    }
+
+    pub fn is_code(&self, addr: usize) -> bool {
+        self.code.contains(&addr)
+    }
+
+    pub fn events(&self) -> &[SelfModification] {
+        &self.events
+    }
+
+    pub fn summary(&self) -> String {
+        let modified: BTreeSet<_> = self.events.iter().map(|e| e.address).collect();
+        let mut out = format!(
+            "{} write(s) to executed code at {} address(es)\n",
+            self.events.len(),
+            modified.len()
+        );
+        for event in &self.events {
+            out.push_str(&format!(
+                "pc {:>5}: @{} {} -> {}\n",
+                event.pc, event.address, event.old, event.new
+            ));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::inconsistent_digit_grouping)]
+mod self_modification_tests {
+    use super::*;
+    use crate::Computer;
+
+    #[test]
+    fn flags_writes_to_executed_instructions() {
+        let program: Vec<i64> = vec![
+            1_04,  // 0: output
+            7,     // 1: 7 (incremented on each pass)
+            10_01, // 2: add
+            1,     // 3: @1
+            1,     // 4: 1
+            1,     // 5: ... to @1
+            10_08, // 6: equals
+            1,     // 7: @1
+            9,     // 8: 9
+            14,    // 9: ... to @14
+            10_06, // 10: jump if false
+            14,    // 11: @14
+            0,     // 12: to @0
+            99,    // 13: halt
+            0,     // 14
+        ];
+        let mut computer = Computer::load(program);
+        computer.enable_self_modification_tracking();
+        let out = computer.run(vec![]).output;
+        assert_eq!(out, vec![7, 8]);
+        let tracker = computer.self_modifications().unwrap();
+        assert_eq!(
+            tracker.events(),
+            &[
+                SelfModification {
+                    pc: 2,
+                    address: 1,
+                    old: 7,
+                    new: 8,
+                },
+                SelfModification {
+                    pc: 2,
+                    address: 1,
+                    old: 8,
+                    new: 9,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_writes_to_data() {
+        let program: Vec<i64> = vec![
+            11_01, // 0: add
+            1,     // 1: 1
+            2,     // 2: 2
+            5,     // 3: ... to @5
+            99,    // 4: halt
+            0,     // 5
+        ];
+        let mut computer = Computer::load(program);
+        computer.enable_self_modification_tracking();
+        computer.run(vec![]);
+        let tracker = computer.self_modifications().unwrap();
+        assert!(tracker.events().is_empty());
+        assert!(tracker.is_code(3));
+        assert!(!tracker.is_code(5));
+    }
+
+    #[test]
+    fn flags_instructions_overwriting_their_own_parameters() {
+        let program: Vec<i64> = vec![
+            1,  // 0: add
+            0,  // 1: @0 = 1
+            0,  // 2: @0 = 1
+            3,  // 3: ... to @3
+            99, // 4: halt
+        ];
+        let mut computer = Computer::load(program);
+        computer.enable_self_modification_tracking();
+        computer.run(vec![]);
+        let tracker = computer.self_modifications().unwrap();
+        assert_eq!(
+            tracker.summary(),
+            "1 write(s) to executed code at 1 address(es)\npc     0: @3 3 -> 2\n"
+        );
+    }
+}
@@ -0,0 +1,474 @@
+use crate::{Computer, RunResult};
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    Input(String),
+    Output(String),
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::Input(s) => write!(f, "> {}", s.escape_debug()),
+            Event::Output(s) => write!(f, "< {}", s.escape_debug()),
+        }
+    }
+}
+
+/// A log of the values passed in and out of a `Computer`. In ASCII mode each event is a
+/// line of text including its newline, or the text after the last newline of a run.
+/// Otherwise each event is a single value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Session {
+    ascii: bool,
+    events: Vec<Event>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Divergence {
+    pub event: usize,
+    pub expected: Option<Event>,
+    pub actual: Option<Event>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SessionError {
+    /// A value outside 0..=127 passed in or out in ASCII mode.
+    NotAscii(i64),
+    Diverged(Divergence),
+    /// The session file could not be read.
+    Io(String),
+    /// The session text is not in the format written by `Session`.
+    Malformed(String),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SessionError::NotAscii(v) => write!(f, "value {} is not ASCII", v),
+            SessionError::Diverged(divergence) => write!(f, "{}", divergence),
+            SessionError::Io(e) => write!(f, "cannot read session: {}", e),
+            SessionError::Malformed(e) => write!(f, "malformed session: {}", e),
+        }
+    }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let show = |e: &Option<Event>| match e {
+            Some(e) => e.to_string(),
+            None => "<nothing>".to_owned(),
+        };
+        write!(
+            f,
+            "session diverged at event {}: expected {}, got {}",
+            self.event,
+            show(&self.expected),
+            show(&self.actual)
+        )
+    }
+}
+
+impl Session {
+    pub fn new(ascii: bool) -> Self {
+        Self {
+            ascii,
+            events: vec![],
+        }
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// The recorded inputs, without the newlines ending them in ASCII mode.
+    pub fn inputs(&self) -> impl Iterator<Item = &str> {
+        self.events.iter().filter_map(|e| match e {
+            Event::Input(s) => Some(s.strip_suffix('\n').unwrap_or(s)),
+            Event::Output(_) => None,
+        })
+    }
+
+    pub fn parse(s: &str) -> Result<Self, SessionError> {
+        let mut lines = s.lines();
+        let ascii = match lines.next() {
+            Some("mode: ascii") => true,
+            Some("mode: values") => false,
+            other => {
+                return Err(SessionError::Malformed(format!(
+                    "unexpected header: {:?}",
+                    other
+                )))
+            }
+        };
+        let events = lines
+            .map(|line| {
+                let bad_line = || SessionError::Malformed(format!("unexpected line: {}", line));
+                let make = match line.get(..2) {
+                    Some("> ") => Event::Input,
+                    Some("< ") => Event::Output,
+                    _ => return Err(bad_line()),
+                };
+                let text = &line[2..];
+                if !ascii {
+                    return match text.parse::<i64>() {
+                        Ok(_) => Ok(make(text.to_owned())),
+                        Err(_) => Err(bad_line()),
+                    };
+                }
+                unescape(text).map(make).ok_or_else(bad_line)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { ascii, events })
+    }
+
+    pub fn load(path: &str) -> Result<Self, SessionError> {
+        let text = std::fs::read_to_string(path).map_err(|e| SessionError::Io(e.to_string()))?;
+        Self::parse(&text)
+    }
+
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+
+    fn to_events(
+        &self,
+        values: &[i64],
+        make: fn(String) -> Event,
+    ) -> Result<Vec<Event>, SessionError> {
+        if !self.ascii {
+            return Ok(values.iter().map(|v| make(v.to_string())).collect());
+        }
+        let text = values
+            .iter()
+            .map(|&v| match v {
+                0..=127 => Ok(v as u8 as char),
+                _ => Err(SessionError::NotAscii(v)),
+            })
+            .collect::<Result<String, _>>()?;
+        Ok(text
+            .split_inclusive('\n')
+            .map(|l| make(l.to_owned()))
+            .collect())
+    }
+
+    fn to_values(&self, inputs: &[&str]) -> Result<Vec<i64>, SessionError> {
+        if !self.ascii {
+            return inputs
+                .iter()
+                .map(|s| {
+                    s.parse()
+                        .map_err(|_| SessionError::Malformed(format!("not a value: {}", s)))
+                })
+                .collect();
+        }
+        Ok(inputs
+            .iter()
+            .flat_map(|text| text.chars().map(|c| c as i64))
+            .collect())
+    }
+
+    /// Feeds the recorded inputs to `computer` and checks that it produces the recorded
+    /// outputs, returning the first point at which they differ.
+    pub fn replay(&self, computer: &mut Computer) -> Result<(), SessionError> {
+        let mut i = 0;
+        while i < self.events.len() {
+            let mut inputs = vec![];
+            while let Some(Event::Input(s)) = self.events.get(i) {
+                inputs.push(s.as_str());
+                i += 1;
+            }
+            let output = computer.run(self.to_values(&inputs)?).output;
+            let mut actual = self.to_events(&output, Event::Output)?.into_iter();
+            loop {
+                let expected = match self.events.get(i) {
+                    Some(e @ Event::Output(_)) => Some(e.clone()),
+                    _ => None,
+                };
+                let got = actual.next();
+                if expected != got {
+                    return Err(SessionError::Diverged(Divergence {
+                        event: i,
+                        expected,
+                        actual: got,
+                    }));
+                }
+                if expected.is_none() {
+                    break;
+                }
+                i += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Session {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "mode: {}", if self.ascii { "ascii" } else { "values" })?;
+        for event in &self.events {
+            let (prefix, text) = match event {
+                Event::Input(s) => ("> ", s),
+                Event::Output(s) => ("< ", s),
+            };
+            if self.ascii {
+                writeln!(f, "{}{}", prefix, escape(text))?;
+            } else {
+                writeln!(f, "{}{}", prefix, text)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Writes a line of ASCII text on a line of its own. Backslashes are doubled, and text that
+// didn't end with a newline gets a trailing backslash.
+fn escape(text: &str) -> String {
+    match text.strip_suffix('\n') {
+        Some(line) => line.replace('\\', "\\\\"),
+        None => format!("{}\\", text.replace('\\', "\\\\")),
+    }
+}
+
+fn unescape(line: &str) -> Option<String> {
+    let mut text = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if !c.is_ascii() {
+            return None;
+        }
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => text.push('\\'),
+            None => return Some(text),
+            Some(_) => return None,
+        }
+    }
+    text.push('\n');
+    Some(text)
+}
+
+/// Wraps a `Computer` and logs every value passed to and returned from `run`.
+pub struct SessionRecorder {
+    computer: Computer,
+    session: Session,
+}
+
+impl SessionRecorder {
+    pub fn new(computer: Computer, ascii: bool) -> Self {
+        Self {
+            computer,
+            session: Session::new(ascii),
+        }
+    }
+
+    /// Runs the computer and records the exchange. In ASCII mode a run with input or
+    /// output that isn't ASCII is refused, leaving both the computer and the log as they
+    /// were.
+    pub fn run(&mut self, input: Vec<i64>) -> Result<RunResult, SessionError> {
+        let inputs = self.session.to_events(&input, Event::Input)?;
+        let mut computer = self.computer.clone();
+        let result = computer.run(input);
+        let outputs = self.session.to_events(&result.output, Event::Output)?;
+        self.computer = computer;
+        self.session.events.extend(inputs);
+        self.session.events.extend(outputs);
+        Ok(result)
+    }
+
+    pub fn computer(&self) -> &Computer {
+        &self.computer
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+}
+
+#[cfg(test)]
+mod session_tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    fn echo_doubled() -> Computer {
+        Computer::load(vec![
+            3,    // 0: input
+            11,   // 1: ... to @11
+            1002, // 2: multiply
+            11,   // 3: @11
+            2,    // 4: 2
+            11,   // 5: ... to @11
+            4,    // 6: output
+            11,   // 7: @11
+            1105, // 8: jump if true
+            1,    // 9: true
+            0,    // 10: to @0
+            0,    // 11
+        ])
+    }
+
+    fn echo_uppercase() -> Computer {
+        Computer::load(vec![
+            3,    // 0: input
+            20,   // 1: ... to @20
+            1007, // 2: less-than
+            20,   // 3: @20
+            97,   // 4: 'a'
+            21,   // 5: ... to @21
+            1005, // 6: jump if true
+            21,   // 7: @21
+            13,   // 8: to @13
+            1001, // 9: add
+            20,   // 10: @20
+            -32,  // 11: -32
+            20,   // 12: ... to @20
+            4,    // 13: output
+            20,   // 14: @20
+            1105, // 15: jump if true
+            1,    // 16: true
+            0,    // 17: to @0
+            0,    // 18
+            0,    // 19
+            0,    // 20
+            0,    // 21
+        ])
+    }
+
+    #[test]
+    fn records_values() {
+        let mut recorder = SessionRecorder::new(echo_doubled(), false);
+        recorder.run(vec![1, 2]).unwrap();
+        recorder.run(vec![]).unwrap();
+        recorder.run(vec![5]).unwrap();
+        assert_eq!(
+            recorder.session().to_string(),
+            "mode: values\n> 1\n> 2\n< 2\n< 4\n> 5\n< 10\n"
+        );
+    }
+
+    #[test]
+    fn records_lines_in_ascii_mode() {
+        let mut recorder = SessionRecorder::new(echo_uppercase(), true);
+        let input = "north\ntake sand\n".chars().map(|c| c as i64).collect();
+        let out = recorder.run(input).unwrap().output;
+        let out: String = out
+            .iter()
+            .map(|&c| u8::try_from(c).unwrap() as char)
+            .collect();
+        assert_eq!(out, "NORTH\nTAKE SAND\n");
+        assert_eq!(
+            recorder.session().to_string(),
+            "mode: ascii\n> north\n> take sand\n< NORTH\n< TAKE SAND\n"
+        );
+        assert_eq!(
+            recorder.session().inputs().collect::<Vec<_>>(),
+            vec!["north", "take sand"]
+        );
+    }
+
+    #[test]
+    fn replays_recorded_sessions() {
+        let mut recorder = SessionRecorder::new(echo_uppercase(), true);
+        recorder
+            .run("west\n".chars().map(|c| c as i64).collect())
+            .unwrap();
+        recorder
+            .run("east\n".chars().map(|c| c as i64).collect())
+            .unwrap();
+        let session = Session::parse(&recorder.session().to_string()).unwrap();
+        assert_eq!(&session, recorder.session());
+        assert_eq!(session.replay(&mut echo_uppercase()), Ok(()));
+    }
+
+    #[test]
+    fn keeps_lines_without_newlines() {
+        let mut recorder = SessionRecorder::new(echo_uppercase(), true);
+        for text in &["we", "st\\\n", "no\nrth"] {
+            recorder
+                .run(text.chars().map(|c| c as i64).collect())
+                .unwrap();
+        }
+        let text = recorder.session().to_string();
+        assert_eq!(
+            text,
+            "mode: ascii\n> we\\\n< WE\\\n> st\\\\\n< ST\\\\\n> no\n> rth\\\n< NO\n< RTH\\\n"
+        );
+        assert_eq!(
+            recorder.session().inputs().collect::<Vec<_>>(),
+            vec!["we", "st\\", "no", "rth"]
+        );
+        let session = Session::parse(&text).unwrap();
+        assert_eq!(&session, recorder.session());
+        assert_eq!(session.replay(&mut echo_uppercase()), Ok(()));
+    }
+
+    #[test]
+    fn refuses_values_that_are_not_ascii() {
+        let mut recorder = SessionRecorder::new(echo_doubled(), true);
+        assert_eq!(recorder.run(vec![200]), Err(SessionError::NotAscii(200)));
+        assert_eq!(recorder.computer().memory()[11], 0);
+        assert_eq!(recorder.run(vec![100]), Err(SessionError::NotAscii(200)));
+        assert_eq!(recorder.computer().memory()[11], 0);
+        assert_eq!(recorder.session().events(), &[]);
+        assert_eq!(recorder.run(vec![10]).unwrap().output, vec![20]);
+    }
+
+    #[test]
+    fn refuses_malformed_sessions() {
+        let malformed = |s: &str| match Session::parse(s) {
+            Err(SessionError::Malformed(e)) => e,
+            other => panic!("expected a malformed session, got {:?}", other),
+        };
+        assert_eq!(malformed(""), "unexpected header: None");
+        assert_eq!(
+            malformed("mode: text\n"),
+            "unexpected header: Some(\"mode: text\")"
+        );
+        assert_eq!(malformed("mode: values\n? 1\n"), "unexpected line: ? 1");
+        assert_eq!(malformed("mode: values\n> one\n"), "unexpected line: > one");
+        assert_eq!(
+            malformed("mode: ascii\n> a\\b\n"),
+            "unexpected line: > a\\b"
+        );
+        assert!(matches!(
+            Session::load("no/such/session"),
+            Err(SessionError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn reports_first_divergence() {
+        let session = Session::parse("mode: values\n> 1\n< 2\n> 3\n< 7\n> 4\n< 8\n").unwrap();
+        assert_eq!(
+            session.replay(&mut echo_doubled()),
+            Err(SessionError::Diverged(Divergence {
+                event: 3,
+                expected: Some(Event::Output("7".to_owned())),
+                actual: Some(Event::Output("6".to_owned())),
+            }))
+        );
+    }
+
+    #[test]
+    fn reports_missing_and_unexpected_outputs() {
+        let session = Session::parse("mode: values\n> 1\n< 2\n< 4\n").unwrap();
+        let err = session.replay(&mut echo_doubled()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "session diverged at event 2: expected < 4, got <nothing>"
+        );
+
+        let session = Session::parse("mode: values\n> 1\n> 2\n< 2\n").unwrap();
+        match session.replay(&mut echo_doubled()) {
+            Err(SessionError::Diverged(divergence)) => {
+                assert_eq!(divergence.event, 3);
+                assert_eq!(divergence.actual, Some(Event::Output("4".to_owned())));
+            }
+            other => panic!("expected a divergence, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,757 @@
+use crate::{decode_modes, Mode, ModeGenerator, Opcode, MEMORY};
+use num_traits::FromPrimitive;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+
+const MAX_STEPS: usize = 10_000;
+const MAX_PATHS: usize = 1_000;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Expr {
+    Const(i64),
+    Var(String),
+    Add(Rc<Expr>, Rc<Expr>),
+    Mul(Rc<Expr>, Rc<Expr>),
+    LessThan(Rc<Expr>, Rc<Expr>),
+    Equals(Rc<Expr>, Rc<Expr>),
+    /// A read from a memory address that was not known at the time of the read.
+    Load(Rc<Expr>),
+}
+
+impl Expr {
+    pub fn var(name: &str) -> Self {
+        Expr::Var(name.to_owned())
+    }
+
+    pub fn sum(a: Expr, b: Expr) -> Self {
+        match (a, b) {
+            (Expr::Const(x), Expr::Const(y)) => Expr::Const(x + y),
+            (Expr::Const(0), e) | (e, Expr::Const(0)) => e,
+            (a, b) => Expr::Add(Rc::new(a), Rc::new(b)),
+        }
+    }
+
+    pub fn product(a: Expr, b: Expr) -> Self {
+        match (a, b) {
+            (Expr::Const(x), Expr::Const(y)) => Expr::Const(x * y),
+            (Expr::Const(0), _) | (_, Expr::Const(0)) => Expr::Const(0),
+            (Expr::Const(1), e) | (e, Expr::Const(1)) => e,
+            (a, b) => Expr::Mul(Rc::new(a), Rc::new(b)),
+        }
+    }
+
+    pub fn less_than(a: Expr, b: Expr) -> Self {
+        match (a, b) {
+            (Expr::Const(x), Expr::Const(y)) => Expr::Const((x < y) as i64),
+            (a, b) => Expr::LessThan(Rc::new(a), Rc::new(b)),
+        }
+    }
+
+    pub fn equals(a: Expr, b: Expr) -> Self {
+        match (a, b) {
+            (Expr::Const(x), Expr::Const(y)) => Expr::Const((x == y) as i64),
+            (a, b) if a == b => Expr::Const(1),
+            (a, b) => Expr::Equals(Rc::new(a), Rc::new(b)),
+        }
+    }
+
+    pub fn as_const(&self) -> Option<i64> {
+        match self {
+            Expr::Const(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Evaluates the expression with the given variable values. Returns `None` if a variable
+    /// is missing or the expression contains an unresolved `Load`.
+    pub fn eval(&self, env: &HashMap<String, i64>) -> Option<i64> {
+        Some(match self {
+            Expr::Const(v) => *v,
+            Expr::Var(name) => *env.get(name)?,
+            Expr::Add(a, b) => a.eval(env)? + b.eval(env)?,
+            Expr::Mul(a, b) => a.eval(env)? * b.eval(env)?,
+            Expr::LessThan(a, b) => (a.eval(env)? < b.eval(env)?) as i64,
+            Expr::Equals(a, b) => (a.eval(env)? == b.eval(env)?) as i64,
+            Expr::Load(_) => return None,
+        })
+    }
+
+    pub fn variables(&self) -> BTreeSet<String> {
+        let mut vars = BTreeSet::new();
+        self.collect_variables(&mut vars);
+        vars
+    }
+
+    fn collect_variables(&self, vars: &mut BTreeSet<String>) {
+        match self {
+            Expr::Const(_) => {}
+            Expr::Var(name) => {
+                vars.insert(name.clone());
+            }
+            Expr::Add(a, b) | Expr::Mul(a, b) | Expr::LessThan(a, b) | Expr::Equals(a, b) => {
+                a.collect_variables(vars);
+                b.collect_variables(vars);
+            }
+            Expr::Load(a) => a.collect_variables(vars),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Const(v) => write!(f, "{}", v),
+            Expr::Var(name) => write!(f, "{}", name),
+            Expr::Add(a, b) => write!(f, "({} + {})", a, b),
+            Expr::Mul(a, b) => write!(f, "({} * {})", a, b),
+            Expr::LessThan(a, b) => write!(f, "({} < {})", a, b),
+            Expr::Equals(a, b) => write!(f, "({} == {})", a, b),
+            Expr::Load(a) => write!(f, "mem[{}]", a),
+        }
+    }
+}
+
+/// An expression of the form `constant + sum(coefficient * variable)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Linear {
+    pub constant: i64,
+    pub terms: BTreeMap<String, i64>,
+}
+
+impl Linear {
+    pub fn from_expr(expr: &Expr) -> Option<Self> {
+        match expr {
+            Expr::Const(v) => Some(Self {
+                constant: *v,
+                terms: BTreeMap::new(),
+            }),
+            Expr::Var(name) => {
+                let mut terms = BTreeMap::new();
+                terms.insert(name.clone(), 1);
+                Some(Self { constant: 0, terms })
+            }
+            Expr::Add(a, b) => {
+                let mut lin = Self::from_expr(a)?;
+                let other = Self::from_expr(b)?;
+                lin.constant += other.constant;
+                for (name, coeff) in other.terms {
+                    *lin.terms.entry(name).or_insert(0) += coeff;
+                }
+                lin.terms.retain(|_, coeff| *coeff != 0);
+                Some(lin)
+            }
+            Expr::Mul(a, b) => {
+                let a = Self::from_expr(a)?;
+                let b = Self::from_expr(b)?;
+                let (factor, mut lin) = if a.terms.is_empty() {
+                    (a.constant, b)
+                } else if b.terms.is_empty() {
+                    (b.constant, a)
+                } else {
+                    return None;
+                };
+                lin.constant *= factor;
+                for coeff in lin.terms.values_mut() {
+                    *coeff *= factor;
+                }
+                lin.terms.retain(|_, coeff| *coeff != 0);
+                Some(lin)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Finds every assignment of the ranged variables for which `expr` evaluates to `target`.
+/// Fails if `expr` is not linear or uses a variable without a range. Only the last
+/// variable appearing in `expr` is solved for directly; the others are enumerated over
+/// their ranges.
+pub fn solve_linear(
+    expr: &Expr,
+    target: i64,
+    ranges: &[(&str, RangeInclusive<i64>)],
+) -> Result<Vec<BTreeMap<String, i64>>, String> {
+    let linear = Linear::from_expr(expr).ok_or_else(|| format!("not linear: {}", expr))?;
+    for name in linear.terms.keys() {
+        if !ranges.iter().any(|(n, _)| n == name) {
+            return Err(format!("no range given for variable: {}", name));
+        }
+    }
+    let solved = ranges
+        .iter()
+        .rposition(|(name, _)| linear.terms.contains_key(*name));
+    let mut solutions = vec![];
+    let mut assignment = BTreeMap::new();
+    enumerate_linear(
+        &linear,
+        target,
+        ranges,
+        solved,
+        0,
+        &mut assignment,
+        &mut solutions,
+    );
+    Ok(solutions)
+}
+
+fn enumerate_linear(
+    linear: &Linear,
+    target: i64,
+    ranges: &[(&str, RangeInclusive<i64>)],
+    solved: Option<usize>,
+    index: usize,
+    assignment: &mut BTreeMap<String, i64>,
+    solutions: &mut Vec<BTreeMap<String, i64>>,
+) {
+    if index == ranges.len() {
+        if let Some(solved) = solved {
+            let (name, range) = &ranges[solved];
+            let coeff = linear.terms[*name];
+            let rest = linear.constant
+                + linear
+                    .terms
+                    .iter()
+                    .filter(|(n, _)| n != name)
+                    .map(|(n, c)| c * assignment[n])
+                    .sum::<i64>();
+            let remainder = target - rest;
+            if remainder % coeff != 0 || !range.contains(&(remainder / coeff)) {
+                return;
+            }
+            let mut solution = assignment.clone();
+            solution.insert(name.to_string(), remainder / coeff);
+            solutions.push(solution);
+        } else if linear.constant == target {
+            solutions.push(assignment.clone());
+        }
+        return;
+    }
+    if Some(index) == solved {
+        enumerate_linear(
+            linear,
+            target,
+            ranges,
+            solved,
+            index + 1,
+            assignment,
+            solutions,
+        );
+        return;
+    }
+    let (name, range) = &ranges[index];
+    for value in range.clone() {
+        assignment.insert(name.to_string(), value);
+        enumerate_linear(
+            linear,
+            target,
+            ranges,
+            solved,
+            index + 1,
+            assignment,
+            solutions,
+        );
+    }
+    assignment.remove(*name);
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathEnd {
+    Halted,
+    NeedsInput,
+    StepLimit,
+    /// The path forked after `max_paths` paths were already known and was not explored.
+    PathLimit,
+    Unsupported(String),
+}
+
+#[derive(Clone)]
+struct Memory {
+    program: Rc<Vec<i64>>,
+    writes: HashMap<usize, Expr>,
+}
+
+impl Memory {
+    fn get(&self, addr: usize) -> Expr {
+        match self.writes.get(&addr) {
+            Some(e) => e.clone(),
+            None => Expr::Const(*self.program.get(addr).unwrap_or(&0)),
+        }
+    }
+}
+
+/// A single execution path. Each constraint is an expression that was non-zero when this
+/// path was taken.
+#[derive(Clone)]
+pub struct Path {
+    pub outputs: Vec<Expr>,
+    pub constraints: Vec<Expr>,
+    pub end: PathEnd,
+    memory: Memory,
+}
+
+impl Path {
+    pub fn value_at(&self, addr: usize) -> Expr {
+        self.memory.get(addr)
+    }
+
+    /// Checks whether the given variable values would lead execution down this path.
+    pub fn is_satisfied_by(&self, env: &HashMap<String, i64>) -> Option<bool> {
+        for constraint in &self.constraints {
+            if constraint.eval(env)? == 0 {
+                return Some(false);
+            }
+        }
+        Some(true)
+    }
+}
+
+#[derive(Clone)]
+struct State {
+    memory: Memory,
+    input: VecDeque<Expr>,
+    outputs: Vec<Expr>,
+    constraints: Vec<Expr>,
+    pos: usize,
+    relative_base: i64,
+    steps: usize,
+}
+
+impl State {
+    fn finish(self, end: PathEnd) -> Path {
+        Path {
+            outputs: self.outputs,
+            constraints: self.constraints,
+            end,
+            memory: self.memory,
+        }
+    }
+
+    fn read(&mut self) -> Expr {
+        let val = self.memory.get(self.pos);
+        self.pos += 1;
+        val
+    }
+
+    fn read_param(&mut self, mode_gen: &mut ModeGenerator) -> Expr {
+        let v = self.read();
+        let addr = match mode_gen.next() {
+            Mode::Immediate => return v,
+            Mode::Position => v,
+            Mode::Relative => Expr::sum(Expr::Const(self.relative_base), v),
+        };
+        match addr.as_const() {
+            Some(addr) => self.memory.get(addr as usize),
+            None => Expr::Load(Rc::new(addr)),
+        }
+    }
+
+    fn read_dest_addr(&mut self, mode_gen: &mut ModeGenerator) -> Result<usize, String> {
+        let v = self.read();
+        let addr = match mode_gen.next() {
+            Mode::Position => v,
+            Mode::Relative => Expr::sum(Expr::Const(self.relative_base), v),
+            Mode::Immediate => return Err(format!("immediate destination at {}", self.pos - 1)),
+        };
+        match addr.as_const() {
+            Some(addr) => Ok(addr as usize),
+            None => Err(format!("write to symbolic address {}", addr)),
+        }
+    }
+
+    fn write(&mut self, addr: usize, value: Expr) {
+        self.memory.writes.insert(addr, value);
+    }
+
+    fn is_assumed(&self, cond: &Expr) -> Option<bool> {
+        let negated = Expr::equals(cond.clone(), Expr::Const(0));
+        for constraint in &self.constraints {
+            if constraint == cond {
+                return Some(true);
+            }
+            if *constraint == negated {
+                return Some(false);
+            }
+        }
+        None
+    }
+}
+
+enum Step {
+    Continue,
+    Fork(State),
+    End(PathEnd),
+}
+
+pub struct SymbolicComputer {
+    memory: Memory,
+    max_steps: usize,
+    max_paths: usize,
+}
+
+impl SymbolicComputer {
+    pub fn load(mut program: Vec<i64>) -> Self {
+        if program.len() < MEMORY {
+            program.resize(MEMORY, 0);
+        }
+        Self {
+            memory: Memory {
+                program: Rc::new(program),
+                writes: HashMap::new(),
+            },
+            max_steps: MAX_STEPS,
+            max_paths: MAX_PATHS,
+        }
+    }
+
+    pub fn set_address(&mut self, address: usize, value: Expr) {
+        self.memory.writes.insert(address, value);
+    }
+
+    pub fn set_symbol(&mut self, address: usize, name: &str) {
+        self.set_address(address, Expr::var(name));
+    }
+
+    pub fn set_limits(&mut self, max_steps: usize, max_paths: usize) {
+        self.max_steps = max_steps;
+        self.max_paths = max_paths;
+    }
+
+    /// Runs every feasible path through the program, forking whenever a jump depends on a
+    /// symbolic value. Paths are explored breadth-first and forks beyond `max_paths` are
+    /// returned unexplored.
+    pub fn explore(&self, input: Vec<Expr>) -> Vec<Path> {
+        let mut pending: VecDeque<_> = vec![State {
+            memory: self.memory.clone(),
+            input: input.into(),
+            outputs: vec![],
+            constraints: vec![],
+            pos: 0,
+            relative_base: 0,
+            steps: 0,
+        }]
+        .into();
+        let mut paths = vec![];
+        while let Some(mut state) = pending.pop_front() {
+            let end = loop {
+                if state.steps >= self.max_steps {
+                    break Some(PathEnd::StepLimit);
+                }
+                state.steps += 1;
+                match Self::step(&mut state) {
+                    Step::Continue => {}
+                    Step::Fork(other) => {
+                        if paths.len() + pending.len() + 2 > self.max_paths {
+                            paths.push(other.finish(PathEnd::PathLimit));
+                            continue;
+                        }
+                        pending.push_back(other);
+                        break None;
+                    }
+                    Step::End(end) => break Some(end),
+                }
+            };
+            match end {
+                Some(end) => paths.push(state.finish(end)),
+                None => pending.push_back(state),
+            }
+        }
+        paths
+    }
+
+    fn step(state: &mut State) -> Step {
+        let start_pos = state.pos;
+        let val = match state.read().as_const() {
+            Some(val) => val,
+            None => {
+                return Step::End(PathEnd::Unsupported(format!(
+                    "symbolic instruction at {}",
+                    start_pos
+                )))
+            }
+        };
+        let opcode: Opcode = match FromPrimitive::from_i64(val % 100) {
+            Some(opcode) => opcode,
+            None => {
+                return Step::End(PathEnd::Unsupported(format!(
+                    "unexpected opcode {} at {}",
+                    val % 100,
+                    start_pos
+                )))
+            }
+        };
+        if decode_modes(val, opcode.num_params()).is_none() {
+            return Step::End(PathEnd::Unsupported(format!(
+                "invalid parameter mode in {} at {}",
+                val, start_pos
+            )));
+        }
+        let mut mg = ModeGenerator { val: val / 100 };
+        let result = match opcode {
+            Opcode::Halt => return Step::End(PathEnd::Halted),
+            Opcode::Input => {
+                if state.input.is_empty() {
+                    state.pos = start_pos;
+                    return Step::End(PathEnd::NeedsInput);
+                }
+                state.read_dest_addr(&mut mg).map(|dest| {
+                    let v = state.input.pop_front().unwrap();
+                    state.write(dest, v);
+                })
+            }
+            Opcode::Output => {
+                let v = state.read_param(&mut mg);
+                state.outputs.push(v);
+                Ok(())
+            }
+            Opcode::AdjustRelativeBase => match state.read_param(&mut mg).as_const() {
+                Some(v) => {
+                    state.relative_base += v;
+                    Ok(())
+                }
+                None => Err(format!("symbolic relative base at {}", start_pos)),
+            },
+            Opcode::Add | Opcode::Multiply | Opcode::LessThan | Opcode::Equals => {
+                let a = state.read_param(&mut mg);
+                let b = state.read_param(&mut mg);
+                state.read_dest_addr(&mut mg).map(|dest| {
+                    let v = match opcode {
+                        Opcode::Add => Expr::sum(a, b),
+                        Opcode::Multiply => Expr::product(a, b),
+                        Opcode::LessThan => Expr::less_than(a, b),
+                        _ => Expr::equals(a, b),
+                    };
+                    state.write(dest, v);
+                })
+            }
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+                let test = state.read_param(&mut mg);
+                let dest = state.read_param(&mut mg);
+                let cond = match opcode {
+                    Opcode::JumpIfTrue => test,
+                    _ => Expr::equals(test, Expr::Const(0)),
+                };
+                return Self::branch(state, cond, dest, start_pos);
+            }
+        };
+        match result {
+            Ok(()) => Step::Continue,
+            Err(msg) => Step::End(PathEnd::Unsupported(msg)),
+        }
+    }
+
+    fn branch(state: &mut State, cond: Expr, dest: Expr, start_pos: usize) -> Step {
+        let taken = match cond.as_const() {
+            Some(v) => Some(v != 0),
+            None => state.is_assumed(&cond),
+        };
+        if taken == Some(false) {
+            return Step::Continue;
+        }
+        let dest = match dest.as_const() {
+            Some(dest) => dest as usize,
+            None => {
+                return Step::End(PathEnd::Unsupported(format!(
+                    "symbolic jump target at {}",
+                    start_pos
+                )))
+            }
+        };
+        if taken == Some(true) {
+            state.pos = dest;
+            return Step::Continue;
+        }
+        let mut other = state.clone();
+        other
+            .constraints
+            .push(Expr::equals(cond.clone(), Expr::Const(0)));
+        state.constraints.push(cond);
+        state.pos = dest;
+        Step::Fork(other)
+    }
+}
+
+#[cfg(test)]
+mod symbolic_tests {
+    use super::*;
+
+    fn env(values: &[(&str, i64)]) -> HashMap<String, i64> {
+        values.iter().map(|(n, v)| (n.to_string(), *v)).collect()
+    }
+
+    #[test]
+    fn builds_expressions_for_straight_line_code() {
+        let program: Vec<i64> = vec![
+            1,  // 0: add
+            9,  // 1: @9 (noun)
+            10, // 2: @10 (verb)
+            0,  // 3: ... to @0
+            2,  // 4: multiply
+            0,  // 5: @0
+            12, // 6: @12 = 3
+            0,  // 7: ... to @0
+            99, // 8: halt
+            0,  // 9
+            0,  // 10
+            0,  // 11
+            3,  // 12
+        ];
+        let mut computer = SymbolicComputer::load(program);
+        computer.set_symbol(9, "noun");
+        computer.set_symbol(10, "verb");
+        let paths = computer.explore(vec![]);
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].end, PathEnd::Halted);
+        assert_eq!(paths[0].value_at(0).to_string(), "((noun + verb) * 3)");
+        let linear = Linear::from_expr(&paths[0].value_at(0)).unwrap();
+        assert_eq!(linear.constant, 0);
+        assert_eq!(linear.terms["noun"], 3);
+        assert_eq!(linear.terms["verb"], 3);
+    }
+
+    #[test]
+    fn forks_on_symbolic_jumps() {
+        let program: Vec<i64> = vec![
+            3,     // 0: input
+            20,    // 1: ... to @20
+            10_08, // 2: equals
+            20,    // 3: @20
+            7,     // 4: 7
+            21,    // 5: ... to @21
+            10_05, // 6: jump if true
+            21,    // 7: @21
+            12,    // 8: to @12
+            1_04,  // 9: output
+            0,     // 10: 0
+            99,    // 11: halt
+            1_04,  // 12: output
+            1,     // 13: 1
+            99,    // 14: halt
+        ];
+        let paths = SymbolicComputer::load(program).explore(vec![Expr::var("x")]);
+        assert_eq!(paths.len(), 2);
+        for (x, expected) in &[(7, 1), (8, 0)] {
+            let env = env(&[("x", *x)]);
+            let matching: Vec<_> = paths
+                .iter()
+                .filter(|p| p.is_satisfied_by(&env) == Some(true))
+                .collect();
+            assert_eq!(matching.len(), 1);
+            assert_eq!(matching[0].outputs, vec![Expr::Const(*expected)]);
+        }
+    }
+
+    #[test]
+    fn does_not_fork_on_previously_decided_conditions() {
+        let program: Vec<i64> = vec![
+            10_05, // 0: jump if true
+            20,    // 1: @20
+            6,     // 2: to @6
+            99,    // 3: halt
+            0,     // 4
+            0,     // 5
+            10_05, // 6: jump if true
+            20,    // 7: @20
+            11,    // 8: to @11
+            1_04,  // 9: output (unreachable)
+            0,     // 10: 0
+            1_04,  // 11: output
+            1,     // 12: 1
+            99,    // 13: halt
+        ];
+        let mut computer = SymbolicComputer::load(program);
+        computer.set_symbol(20, "x");
+        let paths = computer.explore(vec![]);
+        assert_eq!(paths.len(), 2);
+        assert!(paths.iter().all(|p| p.outputs != vec![Expr::Const(0)]));
+    }
+
+    #[test]
+    fn stops_on_invalid_modes() {
+        let paths = SymbolicComputer::load(vec![30001, 0, 0, 0, 99]).explore(vec![]);
+        assert_eq!(
+            paths[0].end,
+            PathEnd::Unsupported("invalid parameter mode in 30001 at 0".to_owned())
+        );
+    }
+
+    #[test]
+    fn reports_when_input_is_exhausted() {
+        let program: Vec<i64> = vec![3, 0, 3, 0, 99];
+        let paths = SymbolicComputer::load(program).explore(vec![Expr::var("a")]);
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].end, PathEnd::NeedsInput);
+        assert_eq!(paths[0].value_at(0), Expr::var("a"));
+    }
+
+    #[test]
+    fn stops_on_unsupported_operations() {
+        let program: Vec<i64> = vec![
+            3,  // 0: input
+            5,  // 1: ... to @5
+            1,  // 2: add
+            0,  // 3: @0
+            0,  // 4: @0
+            0,  // 5: ... to the address given by the input
+            99, // 6: halt
+        ];
+        let paths = SymbolicComputer::load(program).explore(vec![Expr::var("a")]);
+        assert_eq!(
+            paths[0].end,
+            PathEnd::Unsupported("write to symbolic address a".to_owned())
+        );
+    }
+
+    #[test]
+    fn solves_linear_expressions() {
+        let expr = Expr::sum(
+            Expr::product(Expr::Const(100), Expr::var("noun")),
+            Expr::sum(Expr::var("verb"), Expr::Const(7)),
+        );
+        let solutions = solve_linear(&expr, 1234, &[("noun", 0..=99), ("verb", 0..=99)]).unwrap();
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0]["noun"], 12);
+        assert_eq!(solutions[0]["verb"], 27);
+
+        let expr = Expr::sum(Expr::var("a"), Expr::var("b"));
+        let solutions = solve_linear(&expr, 3, &[("a", 0..=9), ("b", 0..=9)]).unwrap();
+        assert_eq!(solutions.len(), 4);
+
+        let expr = Expr::product(Expr::var("a"), Expr::var("b"));
+        assert!(solve_linear(&expr, 3, &[("a", 0..=9), ("b", 0..=9)]).is_err());
+        assert_eq!(
+            solve_linear(&Expr::var("c"), 3, &[("a", 0..=9)]),
+            Err("no range given for variable: c".to_owned())
+        );
+    }
+
+    #[test]
+    fn solves_day_02_directly() {
+        let program: Vec<i64> = std::fs::read_to_string("../../day-02/input")
+            .unwrap()
+            .trim()
+            .split(',')
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let mut computer = SymbolicComputer::load(program.clone());
+        computer.set_symbol(1, "noun");
+        computer.set_symbol(2, "verb");
+        let paths = computer.explore(vec![]);
+        assert_eq!(paths.len(), 1);
+        let solutions = solve_linear(
+            &paths[0].value_at(0),
+            19_690_720,
+            &[("noun", 0..=99), ("verb", 0..=99)],
+        )
+        .unwrap();
+        assert!(!solutions.is_empty());
+        for solution in solutions {
+            let mut concrete = crate::Computer::load(program.clone());
+            concrete.set_address(1, solution["noun"]);
+            concrete.set_address(2, solution["verb"]);
+            concrete.run(vec![]);
+            assert_eq!(concrete.value_at(0), 19_690_720);
+        }
+    }
+}
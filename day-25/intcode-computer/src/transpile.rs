@@ -0,0 +1,529 @@
+use crate::{decode, Mode, Opcode, MEMORY};
+
+const RUNTIME: &str = r#"
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Fault {
+    InvalidOpcode { addr: usize, value: i64 },
+    InvalidMode { addr: usize, value: i64 },
+    ImmediateDestination { addr: usize },
+    AddressOutOfRange { addr: usize, address: i64 },
+    AlreadyHalted,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    Halted,
+    NeedsInput,
+    Fault(Fault),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RunResult {
+    pub output: Vec<i64>,
+    pub instructions: u64,
+    pub stop: StopReason,
+}
+
+#[derive(Clone)]
+pub struct NAME {
+    memory: Vec<i64>,
+    input: std::collections::VecDeque<i64>,
+    output: Vec<i64>,
+    pos: usize,
+    relative_base: i64,
+    modified: Vec<bool>,
+    halted: bool,
+    instructions: u64,
+}
+
+impl NAME {
+    pub fn new() -> Self {
+        let mut memory = PROGRAM.to_vec();
+        if memory.len() < MEMORY {
+            memory.resize(MEMORY, 0);
+        }
+        Self {
+            memory,
+            input: std::collections::VecDeque::new(),
+            output: Vec::new(),
+            pos: 0,
+            relative_base: 0,
+            modified: vec![false; PROGRAM.len()],
+            halted: false,
+            instructions: 0,
+        }
+    }
+
+    pub fn set_address(&mut self, address: usize, value: i64) {
+        self.write(address, value);
+    }
+
+    pub fn run(&mut self, input: Vec<i64>) -> RunResult {
+        self.input = input.into();
+        self.output = Vec::new();
+        self.instructions = 0;
+        let stop = if self.halted {
+            StopReason::Fault(Fault::AlreadyHalted)
+        } else {
+            let stop = loop {
+                match self.pos {
+ARMS
+                    _ => {
+                        if let Some(stop) = self.step() {
+                            break stop;
+                        }
+                    }
+                }
+            };
+            self.halted = stop == StopReason::Halted;
+            stop
+        };
+        RunResult {
+            output: self.output.clone(),
+            instructions: self.instructions,
+            stop,
+        }
+    }
+
+    fn read(&self, addr: usize) -> i64 {
+        *self.memory.get(addr).unwrap_or(&0)
+    }
+
+    fn write(&mut self, addr: usize, value: i64) {
+        if addr < PROGRAM.len() {
+            self.modified[addr] = value != PROGRAM[addr];
+        }
+        self.memory[addr] = value;
+    }
+
+    fn intact(&self, addr: usize, len: usize) -> bool {
+        !self.modified[addr..addr + len].iter().any(|&m| m)
+    }
+
+    fn mode(&self, i: usize) -> i64 {
+        self.read(self.pos) / 10i64.pow(i as u32 + 1) % 10
+    }
+
+    // Checks the instruction at the current position can be executed without corrupting
+    // memory.
+    fn fault(&self) -> Option<Fault> {
+        let addr = self.pos;
+        let value = self.read(addr);
+        let (num_params, dest) = match value % 100 {
+            1 | 2 | 7 | 8 => (3, Some(2)),
+            3 => (1, Some(0)),
+            4 | 9 => (1, None),
+            5 | 6 => (2, None),
+            99 => (0, None),
+            _ => return Some(Fault::InvalidOpcode { addr, value }),
+        };
+        let mut modes = value / 100;
+        for i in 0..num_params {
+            let raw = self.read(addr + 1 + i);
+            let mode = modes % 10;
+            modes /= 10;
+            let address = match mode {
+                1 if dest == Some(i) => return Some(Fault::ImmediateDestination { addr }),
+                1 => continue,
+                0 => raw,
+                2 => self.relative_base + raw,
+                _ => return Some(Fault::InvalidMode { addr, value }),
+            };
+            if address < 0 || (dest == Some(i) && address as usize >= self.memory.len()) {
+                return Some(Fault::AddressOutOfRange { addr, address });
+            }
+        }
+        if modes != 0 {
+            return Some(Fault::InvalidMode { addr, value });
+        }
+        None
+    }
+
+    fn param(&self, i: usize) -> i64 {
+        let raw = self.read(self.pos + i);
+        match self.mode(i) {
+            0 => self.read(raw as usize),
+            1 => raw,
+            _ => self.read((self.relative_base + raw) as usize),
+        }
+    }
+
+    fn dest(&self, i: usize) -> usize {
+        let raw = self.read(self.pos + i);
+        match self.mode(i) {
+            0 => raw as usize,
+            _ => (self.relative_base + raw) as usize,
+        }
+    }
+
+    // Interprets a single instruction. Used for addresses that were not decoded ahead of
+    // time or whose instruction has since been overwritten.
+    fn step(&mut self) -> Option<StopReason> {
+        if let Some(fault) = self.fault() {
+            return Some(StopReason::Fault(fault));
+        }
+        match self.read(self.pos) % 100 {
+            1 | 2 | 7 | 8 => {
+                let (a, b) = (self.param(1), self.param(2));
+                let v = match self.read(self.pos) % 100 {
+                    1 => a + b,
+                    2 => a * b,
+                    7 => (a < b) as i64,
+                    _ => (a == b) as i64,
+                };
+                let dest = self.dest(3);
+                self.write(dest, v);
+                self.pos += 4;
+            }
+            3 => match self.input.pop_front() {
+                Some(v) => {
+                    let dest = self.dest(1);
+                    self.write(dest, v);
+                    self.pos += 2;
+                }
+                None => return Some(StopReason::NeedsInput),
+            },
+            4 => {
+                let v = self.param(1);
+                self.output.push(v);
+                self.pos += 2;
+            }
+            5 | 6 => {
+                let test = self.param(1) != 0;
+                if test == (self.read(self.pos) % 100 == 5) {
+                    self.pos = self.param(2) as usize;
+                } else {
+                    self.pos += 3;
+                }
+            }
+            9 => {
+                self.relative_base += self.param(1);
+                self.pos += 2;
+            }
+            _ => {
+                // Halt is all that's left once the instruction has been checked.
+                self.instructions += 1;
+                return Some(StopReason::Halted);
+            }
+        }
+        self.instructions += 1;
+        None
+    }
+}
+"#;
+
+fn literal(v: i64) -> String {
+    if v < 0 {
+        format!("({})", v)
+    } else {
+        v.to_string()
+    }
+}
+
+fn read_expr(mode: &Mode, v: i64) -> String {
+    match mode {
+        Mode::Position => format!("self.read({})", v),
+        Mode::Immediate => literal(v),
+        Mode::Relative => format!("self.read((self.relative_base + {}) as usize)", literal(v)),
+    }
+}
+
+fn dest_expr(mode: &Mode, v: i64) -> Option<String> {
+    match mode {
+        Mode::Position => Some(v.to_string()),
+        Mode::Relative => Some(format!("(self.relative_base + {}) as usize", literal(v))),
+        Mode::Immediate => None,
+    }
+}
+
+// The code for a pre-decoded instruction, or `None` if the instruction always faults and
+// is left to the interpreter.
+fn arm_body(opcode: &Opcode, params: &[(Mode, i64)], next: usize, memory: usize) -> Option<String> {
+    let dest_param = opcode.dest_param();
+    for (i, (mode, v)) in params.iter().enumerate() {
+        if let Mode::Position = mode {
+            if *v < 0 || (dest_param == Some(i) && *v as usize >= memory) {
+                return None;
+            }
+        }
+    }
+    let read = |i: usize| read_expr(&params[i].0, params[i].1);
+    let dest = |i: usize| dest_expr(&params[i].0, params[i].1);
+    let body = match opcode {
+        Opcode::Halt => "self.instructions += 1;\nbreak StopReason::Halted;".to_owned(),
+        Opcode::Add | Opcode::Multiply | Opcode::LessThan | Opcode::Equals => {
+            let value = match opcode {
+                Opcode::Add => format!("{} + {}", read(0), read(1)),
+                Opcode::Multiply => format!("{} * {}", read(0), read(1)),
+                Opcode::LessThan => format!("({} < {}) as i64", read(0), read(1)),
+                _ => format!("({} == {}) as i64", read(0), read(1)),
+            };
+            format!(
+                "let v = {};\nself.write({}, v);\nself.pos = {};\nself.instructions += 1;",
+                value,
+                dest(2)?,
+                next
+            )
+        }
+        Opcode::Input => format!(
+            "match self.input.pop_front() {{\n    Some(v) => {{\n        self.write({}, v);\n        \
+             self.pos = {};\n        self.instructions += 1;\n    }}\n    \
+             None => break StopReason::NeedsInput,\n}}",
+            dest(0)?,
+            next
+        ),
+        Opcode::Output => format!(
+            "self.output.push({});\nself.pos = {};\nself.instructions += 1;",
+            read(0),
+            next
+        ),
+        Opcode::JumpIfTrue | Opcode::JumpIfFalse => format!(
+            "if {} {} 0 {{\n    self.pos = {} as usize;\n}} else {{\n    self.pos = {};\n}}\n\
+             self.instructions += 1;",
+            read(0),
+            if let Opcode::JumpIfTrue = opcode {
+                "!="
+            } else {
+                "=="
+            },
+            read(1),
+            next
+        ),
+        Opcode::AdjustRelativeBase => format!(
+            "self.relative_base += {};\nself.pos = {};\nself.instructions += 1;",
+            read(0),
+            next
+        ),
+    };
+    // Relative addresses are only known at run time, so are checked before every use.
+    if params
+        .iter()
+        .any(|(mode, _)| matches!(mode, Mode::Relative))
+    {
+        return Some(format!(
+            "if let Some(fault) = self.fault() {{\n    break StopReason::Fault(fault);\n}}\n{}",
+            body
+        ));
+    }
+    Some(body)
+}
+
+/// Translates `program` into standalone Rust source defining a struct called `name` with
+/// `new`, `set_address` and `run` methods that behave like `Computer`, along with its own
+/// `RunResult`, `StopReason` and `Fault` types. Breakpoints and instruction budgets are not
+/// supported. Every address that holds a valid instruction gets a pre-decoded arm in the
+/// dispatch loop; once any word of an instruction is overwritten it falls back to an
+/// interpreter.
+pub fn transpile(program: &[i64], name: &str) -> String {
+    let memory = program.len().max(MEMORY);
+    let mut arms = String::new();
+    for addr in 0..program.len() {
+        let (opcode, params) = match decode(program, addr) {
+            Some(decoded) => decoded,
+            None => continue,
+        };
+        let len = params.len() + 1;
+        if addr + len > program.len() {
+            continue;
+        }
+        let body = match arm_body(&opcode, &params, addr + len, memory) {
+            Some(body) => body,
+            None => continue,
+        };
+        arms.push_str(&format!(
+            "                    {} if self.intact({}, {}) => {{\n",
+            addr, addr, len
+        ));
+        for line in body.lines() {
+            arms.push_str(&format!("                        {}\n", line));
+        }
+        arms.push_str("                    }\n");
+    }
+
+    let words: Vec<String> = program.iter().map(|v| v.to_string()).collect();
+    let mut out = String::new();
+    out.push_str("// Generated from an Intcode program. Do not edit.\n\n");
+    out.push_str(&format!("const MEMORY: usize = {};\n\n", MEMORY));
+    out.push_str(&format!(
+        "const PROGRAM: [i64; {}] = [{}];\n",
+        program.len(),
+        words.join(", ")
+    ));
+    out.push_str(&RUNTIME.replace("NAME", name).replace("ARMS\n", &arms));
+    out
+}
+
+#[cfg(test)]
+mod transpile_tests {
+    use super::*;
+    use crate::Computer;
+    use std::env::temp_dir;
+    use std::process::Command;
+
+    fn read_program(path: &str) -> Vec<i64> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .trim()
+            .split(',')
+            .map(|s| s.parse().unwrap())
+            .collect()
+    }
+
+    struct Case {
+        module: &'static str,
+        program: Vec<i64>,
+        runs: Vec<Vec<Vec<i64>>>,
+    }
+
+    fn self_modifying_program() -> Vec<i64> {
+        vec![
+            104,  // 0: output
+            7,    // 1: 7 (incremented on each pass)
+            1001, // 2: add
+            1,    // 3: @1
+            1,    // 4: 1
+            1,    // 5: ... to @1
+            1008, // 6: equals
+            1,    // 7: @1
+            9,    // 8: 9
+            14,   // 9: ... to @14
+            1006, // 10: jump if false
+            14,   // 11: @14
+            0,    // 12: to @0
+            99,   // 13: halt
+            0,    // 14
+        ]
+    }
+
+    // Jumps to a faulty instruction chosen by the input.
+    fn faulting_program() -> Vec<i64> {
+        vec![
+            3,     // 0: input
+            100,   // 1: ... to @100
+            1_01,  // 2: add
+            7,     // 3: 7
+            100,   // 4: @100
+            101,   // 5: ... to @101
+            10_02, // 6: multiply
+            101,   // 7: @101
+            2,     // 8: 2
+            101,   // 9: ... to @101
+            6,     // 10: jump if false
+            102,   // 11: @102 (zero)
+            101,   // 12: to @101
+            0,     // 13
+            42,    // 14: invalid opcode
+            0,     // 15
+            3_04,  // 16: invalid mode
+            0,     // 17
+            2_04,  // 18: output
+            -5,    // 19: @rb-5
+            11_01, // 20: add
+            1,     // 21: 1
+            1,     // 22: 1
+            -1,    // 23: ... to @-1
+        ]
+    }
+
+    #[test]
+    fn transpiled_programs_match_computer() {
+        let mut beam_runs = vec![];
+        for y in 0..6 {
+            for x in 0..6 {
+                beam_runs.push(vec![vec![x, y]]);
+            }
+        }
+        let cases = vec![
+            Case {
+                module: "self_modifying",
+                program: self_modifying_program(),
+                runs: vec![vec![vec![]]],
+            },
+            Case {
+                module: "halting",
+                program: vec![104, 1, 99, 104, 666, 99],
+                runs: vec![vec![vec![], vec![], vec![]]],
+            },
+            Case {
+                module: "faulting",
+                program: faulting_program(),
+                runs: vec![
+                    vec![vec![0]],
+                    vec![vec![1]],
+                    vec![vec![2]],
+                    vec![vec![3], vec![]],
+                ],
+            },
+            Case {
+                module: "day_05",
+                program: read_program("../../day-05/input"),
+                runs: vec![vec![vec![1]], vec![vec![5]], vec![vec![], vec![8]]],
+            },
+            Case {
+                module: "day_09",
+                program: read_program("../../day-09/input"),
+                runs: vec![vec![vec![1]]],
+            },
+            Case {
+                module: "day_19",
+                program: read_program("../../day-19/input"),
+                runs: beam_runs,
+            },
+        ];
+
+        let mut source = String::new();
+        let mut main = String::from("fn main() {\n");
+        let mut expected = String::new();
+        for case in &cases {
+            source.push_str(&format!(
+                "mod {} {{\n{}\n}}\n",
+                case.module,
+                transpile(&case.program, "Program")
+            ));
+            for runs in &case.runs {
+                let mut computer = Computer::load(case.program.clone());
+                main.push_str(&format!(
+                    "    let mut p = {}::Program::new();\n",
+                    case.module
+                ));
+                for input in runs {
+                    expected.push_str(&format!("{:?}\n", computer.run(input.clone())));
+                    main.push_str(&format!(
+                        "    println!(\"{{:?}}\", p.run(vec!{:?}));\n",
+                        input
+                    ));
+                }
+            }
+        }
+        main.push_str("}\n");
+        source.push_str(&main);
+
+        let dir = temp_dir().join("intcode_transpiled_programs_match_computer");
+        std::fs::create_dir_all(&dir).unwrap();
+        let src = dir.join("main.rs");
+        let bin = dir.join("main");
+        std::fs::write(&src, source).unwrap();
+        let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned());
+        let status = Command::new(rustc)
+            .args(["--edition", "2018", "-O", "-A", "warnings", "-o"])
+            .arg(&bin)
+            .arg(&src)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let output = Command::new(&bin).output().unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+    }
+
+    #[test]
+    fn decodes_instructions_ahead_of_time() {
+        let source = transpile(&[1101, 2, 3, 7, 4, 7, 99, 0], "Adder");
+        assert!(source.contains("pub struct Adder {"));
+        assert!(source.contains("0 if self.intact(0, 4) => {"));
+        assert!(source.contains("let v = 2 + 3;"));
+        assert!(source.contains("4 if self.intact(4, 2) => {"));
+        assert!(source.contains("self.output.push(self.read(7));"));
+        assert!(source.contains("6 if self.intact(6, 1) => {"));
+    }
+}